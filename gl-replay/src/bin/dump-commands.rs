@@ -2,6 +2,7 @@ use docopt::Docopt;
 use serde::Deserialize;
 use std::io;
 
+use gl_replay::dump::{self, GroupNesting};
 use gl_replay::Call;
 use gl_replay::FileRecording;
 
@@ -10,6 +11,9 @@ type Recording = FileRecording<Call>;
 const USAGE: &'static str = "
 Dump gl-replay command log.
 
+Calls between matching debug group or group marker pushes and pops are
+indented by their nesting depth.
+
 Usage:
   gl-replay <dir>...
 ";
@@ -33,8 +37,13 @@ fn main() -> io::Result<()> {
             Ok(recording) => recording,
         };
 
+        let mut nesting = GroupNesting::default();
         for (i, call) in recording.calls.iter().enumerate() {
-            println!("{:4} {:?}", i, call);
+            let indent = nesting.indent(call);
+            match dump::describe_marker(call, &recording.variable) {
+                Some(marker) => println!("{:4} {}{}", i, indent, marker),
+                None => println!("{:4} {}{:?}", i, indent, call),
+            }
         }
     }
 
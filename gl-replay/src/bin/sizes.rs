@@ -2,7 +2,8 @@
 
 #[allow(unused_imports)]
 use gleam::gl::{
-    GLbitfield, GLclampf, GLenum, GLfloat, GLint, GLsizei, GLsizeiptr, GLuint, GLvoid,
+    GLbitfield, GLboolean, GLclampf, GLenum, GLfloat, GLint, GLintptr, GLsizei, GLsizeiptr, GLuint,
+    GLuint64, GLvoid,
 };
 
 #[derive(Copy, Clone, Debug)]
@@ -495,6 +496,7 @@ fn main() {
     struct get_attrib_location {
         program: GLuint,
         name: BufToGl,
+        returned: GLint,
     }
     println!(
         "{} get_attrib_location",
@@ -504,6 +506,7 @@ fn main() {
     struct get_frag_data_location {
         program: GLuint,
         name: BufToGl,
+        returned: GLint,
     }
     println!(
         "{} get_frag_data_location",
@@ -1034,4 +1037,760 @@ fn main() {
         target: GLenum,
     }
     println!("{} generate_mipmap", std::mem::size_of::<generate_mipmap>());
+
+    struct stencil_mask {
+        mask: GLuint,
+    }
+    println!("{} stencil_mask", std::mem::size_of::<stencil_mask>());
+
+    struct stencil_mask_separate {
+        face: GLenum,
+        mask: GLuint,
+    }
+    println!(
+        "{} stencil_mask_separate",
+        std::mem::size_of::<stencil_mask_separate>()
+    );
+
+    struct stencil_func {
+        func: GLenum,
+        ref_: GLint,
+        mask: GLuint,
+    }
+    println!("{} stencil_func", std::mem::size_of::<stencil_func>());
+
+    struct stencil_func_separate {
+        face: GLenum,
+        func: GLenum,
+        ref_: GLint,
+        mask: GLuint,
+    }
+    println!(
+        "{} stencil_func_separate",
+        std::mem::size_of::<stencil_func_separate>()
+    );
+
+    struct stencil_op {
+        sfail: GLenum,
+        dpfail: GLenum,
+        dppass: GLenum,
+    }
+    println!("{} stencil_op", std::mem::size_of::<stencil_op>());
+
+    struct stencil_op_separate {
+        face: GLenum,
+        sfail: GLenum,
+        dpfail: GLenum,
+        dppass: GLenum,
+    }
+    println!(
+        "{} stencil_op_separate",
+        std::mem::size_of::<stencil_op_separate>()
+    );
+
+    struct draw_arrays {
+        mode: GLenum,
+        first: GLint,
+        count: GLsizei,
+    }
+    println!("{} draw_arrays", std::mem::size_of::<draw_arrays>());
+
+    struct draw_arrays_instanced {
+        mode: GLenum,
+        first: GLint,
+        count: GLsizei,
+        primcount: GLsizei,
+    }
+    println!(
+        "{} draw_arrays_instanced",
+        std::mem::size_of::<draw_arrays_instanced>()
+    );
+
+    struct draw_elements {
+        mode: GLenum,
+        count: GLsizei,
+        element_type: GLenum,
+        indices_offset: GLuint,
+    }
+    println!("{} draw_elements", std::mem::size_of::<draw_elements>());
+
+    struct map_buffer {
+        target: GLenum,
+        access: GLbitfield,
+    }
+    println!("{} map_buffer", std::mem::size_of::<map_buffer>());
+
+    struct map_buffer_range {
+        target: GLenum,
+        offset: GLintptr,
+        length: GLsizeiptr,
+        access: GLbitfield,
+    }
+    println!(
+        "{} map_buffer_range",
+        std::mem::size_of::<map_buffer_range>()
+    );
+
+    struct unmap_buffer {
+        target: GLenum,
+        offset: GLintptr,
+        length: GLsizeiptr,
+        access: GLbitfield,
+        data: Option<BufToGl>,
+        untracked: bool,
+        returned: GLboolean,
+    }
+    println!("{} unmap_buffer", std::mem::size_of::<unmap_buffer>());
+
+    struct buffer_sub_data_untyped {
+        target: GLenum,
+        offset: isize,
+        size_data: BufToGl,
+    }
+    println!(
+        "{} buffer_sub_data_untyped",
+        std::mem::size_of::<buffer_sub_data_untyped>()
+    );
+
+    struct compressed_tex_image_2d {
+        target: GLenum,
+        level: GLint,
+        internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei,
+        border: GLint,
+        data: BufToGl,
+        image_size: usize,
+    }
+    println!(
+        "{} compressed_tex_image_2d",
+        std::mem::size_of::<compressed_tex_image_2d>()
+    );
+
+    struct compressed_tex_sub_image_2d {
+        target: GLenum,
+        level: GLint,
+        xoffset: GLint,
+        yoffset: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        data: BufToGl,
+        image_size: usize,
+    }
+    println!(
+        "{} compressed_tex_sub_image_2d",
+        std::mem::size_of::<compressed_tex_sub_image_2d>()
+    );
+
+    struct copy_tex_image_2d {
+        target: GLenum,
+        level: GLint,
+        internal_format: GLenum,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        border: GLint,
+    }
+    println!(
+        "{} copy_tex_image_2d",
+        std::mem::size_of::<copy_tex_image_2d>()
+    );
+
+    struct copy_tex_sub_image_2d {
+        target: GLenum,
+        level: GLint,
+        xoffset: GLint,
+        yoffset: GLint,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+    }
+    println!(
+        "{} copy_tex_sub_image_2d",
+        std::mem::size_of::<copy_tex_sub_image_2d>()
+    );
+
+    struct copy_tex_sub_image_3d {
+        target: GLenum,
+        level: GLint,
+        xoffset: GLint,
+        yoffset: GLint,
+        zoffset: GLint,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+    }
+    println!(
+        "{} copy_tex_sub_image_3d",
+        std::mem::size_of::<copy_tex_sub_image_3d>()
+    );
+
+    struct bind_buffer_base {
+        target: GLenum,
+        index: GLuint,
+        buffer: GLuint,
+    }
+    println!(
+        "{} bind_buffer_base",
+        std::mem::size_of::<bind_buffer_base>()
+    );
+
+    struct bind_buffer_range {
+        target: GLenum,
+        index: GLuint,
+        buffer: GLuint,
+        offset: GLintptr,
+        size: GLsizeiptr,
+    }
+    println!(
+        "{} bind_buffer_range",
+        std::mem::size_of::<bind_buffer_range>()
+    );
+
+    struct uniform_block_binding {
+        program: GLuint,
+        uniform_block_index: GLuint,
+        uniform_block_binding: GLuint,
+    }
+    println!(
+        "{} uniform_block_binding",
+        std::mem::size_of::<uniform_block_binding>()
+    );
+
+    struct get_uniform_block_index {
+        program: GLuint,
+        name: BufToGl,
+        returned: GLuint,
+    }
+    println!(
+        "{} get_uniform_block_index",
+        std::mem::size_of::<get_uniform_block_index>()
+    );
+
+    struct get_uniform_indices {
+        program: GLuint,
+        names: BufToGl,
+        returned: BufFromGl,
+    }
+    println!(
+        "{} get_uniform_indices",
+        std::mem::size_of::<get_uniform_indices>()
+    );
+
+    struct get_active_uniform_block_i {
+        program: GLuint,
+        index: GLuint,
+        pname: GLenum,
+        returned: GLint,
+    }
+    println!(
+        "{} get_active_uniform_block_i",
+        std::mem::size_of::<get_active_uniform_block_i>()
+    );
+
+    struct get_active_uniform_block_iv {
+        program: GLuint,
+        index: GLuint,
+        pname: GLenum,
+        returned: BufFromGl,
+    }
+    println!(
+        "{} get_active_uniform_block_iv",
+        std::mem::size_of::<get_active_uniform_block_iv>()
+    );
+
+    struct get_active_uniform_block_name {
+        program: GLuint,
+        index: GLuint,
+        returned: BufFromGl,
+    }
+    println!(
+        "{} get_active_uniform_block_name",
+        std::mem::size_of::<get_active_uniform_block_name>()
+    );
+
+    struct get_active_attrib {
+        program: GLuint,
+        index: GLuint,
+        returned: (i32, u32, BufFromGl),
+    }
+    println!(
+        "{} get_active_attrib",
+        std::mem::size_of::<get_active_attrib>()
+    );
+
+    struct get_active_uniform {
+        program: GLuint,
+        index: GLuint,
+        returned: (i32, u32, BufFromGl),
+    }
+    println!(
+        "{} get_active_uniform",
+        std::mem::size_of::<get_active_uniform>()
+    );
+
+    struct get_active_uniforms_iv {
+        program: GLuint,
+        indices: BufToGl,
+        pname: GLenum,
+        returned: BufFromGl,
+    }
+    println!(
+        "{} get_active_uniforms_iv",
+        std::mem::size_of::<get_active_uniforms_iv>()
+    );
+
+    struct get_program_info_log {
+        program: GLuint,
+        returned: BufFromGl,
+    }
+    println!(
+        "{} get_program_info_log",
+        std::mem::size_of::<get_program_info_log>()
+    );
+
+    struct get_shader_precision_format {
+        shader_type: GLuint,
+        precision_type: GLuint,
+        returned: (GLint, GLint, GLint),
+    }
+    println!(
+        "{} get_shader_precision_format",
+        std::mem::size_of::<get_shader_precision_format>()
+    );
+
+    struct get_program_binary {
+        program: GLuint,
+        returned: (BufFromGl, GLenum),
+    }
+    println!(
+        "{} get_program_binary",
+        std::mem::size_of::<get_program_binary>()
+    );
+
+    struct program_binary {
+        program: GLuint,
+        format: GLenum,
+        binary: BufToGl,
+    }
+    println!("{} program_binary", std::mem::size_of::<program_binary>());
+
+    struct program_parameter_i {
+        program: GLuint,
+        pname: GLenum,
+        value: GLint,
+    }
+    println!(
+        "{} program_parameter_i",
+        std::mem::size_of::<program_parameter_i>()
+    );
+
+    struct get_vertex_attrib_iv {
+        index: GLuint,
+        pname: GLenum,
+        result: BufFromGl,
+    }
+    println!(
+        "{} get_vertex_attrib_iv",
+        std::mem::size_of::<get_vertex_attrib_iv>()
+    );
+
+    struct get_vertex_attrib_fv {
+        index: GLuint,
+        pname: GLenum,
+        result: BufFromGl,
+    }
+    println!(
+        "{} get_vertex_attrib_fv",
+        std::mem::size_of::<get_vertex_attrib_fv>()
+    );
+
+    struct get_vertex_attrib_pointer_v {
+        index: GLuint,
+        pname: GLenum,
+        returned: GLsizeiptr,
+    }
+    println!(
+        "{} get_vertex_attrib_pointer_v",
+        std::mem::size_of::<get_vertex_attrib_pointer_v>()
+    );
+
+    struct get_buffer_parameter_iv {
+        target: GLuint,
+        pname: GLenum,
+        returned: GLint,
+    }
+    println!(
+        "{} get_buffer_parameter_iv",
+        std::mem::size_of::<get_buffer_parameter_iv>()
+    );
+
+    struct get_framebuffer_attachment_parameter_iv {
+        target: GLenum,
+        attachment: GLenum,
+        pname: GLenum,
+        returned: GLint,
+    }
+    println!(
+        "{} get_framebuffer_attachment_parameter_iv",
+        std::mem::size_of::<get_framebuffer_attachment_parameter_iv>()
+    );
+
+    struct get_renderbuffer_parameter_iv {
+        target: GLenum,
+        pname: GLenum,
+        returned: GLint,
+    }
+    println!(
+        "{} get_renderbuffer_parameter_iv",
+        std::mem::size_of::<get_renderbuffer_parameter_iv>()
+    );
+
+    struct get_tex_parameter_iv {
+        target: GLenum,
+        name: GLenum,
+        returned: GLint,
+    }
+    println!(
+        "{} get_tex_parameter_iv",
+        std::mem::size_of::<get_tex_parameter_iv>()
+    );
+
+    struct get_tex_parameter_fv {
+        target: GLenum,
+        name: GLenum,
+        returned: GLfloat,
+    }
+    println!(
+        "{} get_tex_parameter_fv",
+        std::mem::size_of::<get_tex_parameter_fv>()
+    );
+
+    struct insert_event_marker_ext {
+        message: BufToGl,
+    }
+    println!(
+        "{} insert_event_marker_ext",
+        std::mem::size_of::<insert_event_marker_ext>()
+    );
+
+    struct push_group_marker_ext {
+        message: BufToGl,
+    }
+    println!(
+        "{} push_group_marker_ext",
+        std::mem::size_of::<push_group_marker_ext>()
+    );
+
+    struct pop_group_marker_ext {}
+    println!(
+        "{} pop_group_marker_ext",
+        std::mem::size_of::<pop_group_marker_ext>()
+    );
+
+    struct debug_message_insert_khr {
+        source: GLenum,
+        type_: GLenum,
+        id: GLuint,
+        severity: GLenum,
+        message: BufToGl,
+    }
+    println!(
+        "{} debug_message_insert_khr",
+        std::mem::size_of::<debug_message_insert_khr>()
+    );
+
+    struct push_debug_group_khr {
+        source: GLenum,
+        id: GLuint,
+        message: BufToGl,
+    }
+    println!(
+        "{} push_debug_group_khr",
+        std::mem::size_of::<push_debug_group_khr>()
+    );
+
+    struct pop_debug_group_khr {}
+    println!(
+        "{} pop_debug_group_khr",
+        std::mem::size_of::<pop_debug_group_khr>()
+    );
+
+    struct fence_sync {
+        condition: GLenum,
+        flags: GLbitfield,
+        returned: u32,
+    }
+    println!("{} fence_sync", std::mem::size_of::<fence_sync>());
+
+    struct client_wait_sync {
+        sync: u32,
+        flags: GLbitfield,
+        timeout: GLuint64,
+    }
+    println!(
+        "{} client_wait_sync",
+        std::mem::size_of::<client_wait_sync>()
+    );
+
+    struct wait_sync {
+        sync: u32,
+        flags: GLbitfield,
+        timeout: GLuint64,
+    }
+    println!("{} wait_sync", std::mem::size_of::<wait_sync>());
+
+    struct delete_sync {
+        sync: u32,
+    }
+    println!("{} delete_sync", std::mem::size_of::<delete_sync>());
+
+    struct texture_range_apple {
+        target: GLenum,
+        length: usize,
+    }
+    println!(
+        "{} texture_range_apple",
+        std::mem::size_of::<texture_range_apple>()
+    );
+
+    struct gen_fences_apple {
+        n: GLsizei,
+        returned: BufFromGl,
+    }
+    println!(
+        "{} gen_fences_apple",
+        std::mem::size_of::<gen_fences_apple>()
+    );
+
+    struct delete_fences_apple {
+        fences: BufToGl,
+    }
+    println!(
+        "{} delete_fences_apple",
+        std::mem::size_of::<delete_fences_apple>()
+    );
+
+    struct set_fence_apple {
+        fence: GLuint,
+    }
+    println!("{} set_fence_apple", std::mem::size_of::<set_fence_apple>());
+
+    struct finish_fence_apple {
+        fence: GLuint,
+    }
+    println!(
+        "{} finish_fence_apple",
+        std::mem::size_of::<finish_fence_apple>()
+    );
+
+    struct test_fence_apple {
+        fence: GLuint,
+    }
+    println!(
+        "{} test_fence_apple",
+        std::mem::size_of::<test_fence_apple>()
+    );
+
+    struct test_object_apple {
+        object: GLenum,
+        name: GLuint,
+        returned: GLboolean,
+    }
+    println!(
+        "{} test_object_apple",
+        std::mem::size_of::<test_object_apple>()
+    );
+
+    struct finish_object_apple {
+        object: GLenum,
+        name: GLuint,
+    }
+    println!(
+        "{} finish_object_apple",
+        std::mem::size_of::<finish_object_apple>()
+    );
+
+    struct egl_image_target_texture2d_oes {
+        target: GLenum,
+        pixels: Option<BufToGl>,
+    }
+    println!(
+        "{} egl_image_target_texture2d_oes",
+        std::mem::size_of::<egl_image_target_texture2d_oes>()
+    );
+
+    struct egl_image_target_renderbuffer_storage_oes {
+        target: GLenum,
+        pixels: Option<BufToGl>,
+    }
+    println!(
+        "{} egl_image_target_renderbuffer_storage_oes",
+        std::mem::size_of::<egl_image_target_renderbuffer_storage_oes>()
+    );
+
+    struct copy_texture_chromium {
+        source_id: GLuint,
+        source_level: GLint,
+        dest_target: GLenum,
+        dest_id: GLuint,
+        dest_level: GLint,
+        internal_format: GLint,
+        dest_type: GLenum,
+        unpack_flip_y: GLboolean,
+        unpack_premultiply_alpha: GLboolean,
+        unpack_unmultiply_alpha: GLboolean,
+    }
+    println!(
+        "{} copy_texture_chromium",
+        std::mem::size_of::<copy_texture_chromium>()
+    );
+
+    struct copy_sub_texture_chromium {
+        source_id: GLuint,
+        source_level: GLint,
+        dest_target: GLenum,
+        dest_id: GLuint,
+        dest_level: GLint,
+        x_offset: GLint,
+        y_offset: GLint,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        unpack_flip_y: GLboolean,
+        unpack_premultiply_alpha: GLboolean,
+        unpack_unmultiply_alpha: GLboolean,
+    }
+    println!(
+        "{} copy_sub_texture_chromium",
+        std::mem::size_of::<copy_sub_texture_chromium>()
+    );
+
+    struct copy_texture_3d_angle {
+        source_id: GLuint,
+        source_level: GLint,
+        dest_target: GLenum,
+        dest_id: GLuint,
+        dest_level: GLint,
+        internal_format: GLint,
+        dest_type: GLenum,
+        unpack_flip_y: GLboolean,
+        unpack_premultiply_alpha: GLboolean,
+        unpack_unmultiply_alpha: GLboolean,
+    }
+    println!(
+        "{} copy_texture_3d_angle",
+        std::mem::size_of::<copy_texture_3d_angle>()
+    );
+
+    struct copy_sub_texture_3d_angle {
+        source_id: GLuint,
+        source_level: GLint,
+        dest_target: GLenum,
+        dest_id: GLuint,
+        dest_level: GLint,
+        x_offset: GLint,
+        y_offset: GLint,
+        z_offset: GLint,
+        x: GLint,
+        y: GLint,
+        z: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        depth: GLsizei,
+        unpack_flip_y: GLboolean,
+        unpack_premultiply_alpha: GLboolean,
+        unpack_unmultiply_alpha: GLboolean,
+    }
+    println!(
+        "{} copy_sub_texture_3d_angle",
+        std::mem::size_of::<copy_sub_texture_3d_angle>()
+    );
+
+    struct bind_frag_data_location_indexed {
+        program: GLuint,
+        color_number: GLuint,
+        index: GLuint,
+        name: BufToGl,
+    }
+    println!(
+        "{} bind_frag_data_location_indexed",
+        std::mem::size_of::<bind_frag_data_location_indexed>()
+    );
+
+    struct get_frag_data_index {
+        program: GLuint,
+        name: BufToGl,
+        returned: GLint,
+    }
+    println!(
+        "{} get_frag_data_index",
+        std::mem::size_of::<get_frag_data_index>()
+    );
+
+    struct tex_sub_image_2d {
+        target: GLenum,
+        level: GLint,
+        xoffset: GLint,
+        yoffset: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        ty: GLenum,
+        data: BufToGl,
+    }
+    println!(
+        "{} tex_sub_image_2d",
+        std::mem::size_of::<tex_sub_image_2d>()
+    );
+
+    struct draw_buffers {
+        bufs: BufToGl,
+    }
+    println!("{} draw_buffers", std::mem::size_of::<draw_buffers>());
+
+    struct tex_buffer {
+        target: GLenum,
+        internal_format: GLenum,
+        buffer: GLuint,
+    }
+    println!("{} tex_buffer", std::mem::size_of::<tex_buffer>());
+
+    struct vertex_attrib_4f {
+        index: GLuint,
+        x: GLfloat,
+        y: GLfloat,
+        z: GLfloat,
+        w: GLfloat,
+    }
+    println!(
+        "{} vertex_attrib_4f",
+        std::mem::size_of::<vertex_attrib_4f>()
+    );
+
+    struct vertex_attrib_pointer_f32 {
+        index: GLuint,
+        size: GLint,
+        normalized: bool,
+        stride: GLsizei,
+        offset: GLuint,
+    }
+    println!(
+        "{} vertex_attrib_pointer_f32",
+        std::mem::size_of::<vertex_attrib_pointer_f32>()
+    );
+
+    struct validate_program {
+        program: GLuint,
+        status: GLint,
+    }
+    println!(
+        "{} validate_program",
+        std::mem::size_of::<validate_program>()
+    );
 }
@@ -1,6 +1,9 @@
 //! A representation for recorded `gleam::Gl` method calls.
 
-use gleam::gl::{GLbitfield, GLclampf, GLenum, GLfloat, GLint, GLsizei, GLuint};
+use gleam::gl::{
+    GLbitfield, GLboolean, GLclampf, GLenum, GLfloat, GLint, GLintptr, GLsizei, GLsizeiptr, GLuint,
+    GLuint64,
+};
 
 use std::os::raw::c_int;
 
@@ -15,10 +18,15 @@ unsafe impl raw::Simple for Call {}
 /// The `tex_image_2d`, `tex_sub_image_2d`, `tex_image_3d`, and
 /// `tex_sub_image_3d` methods all take a final data pointer which gets
 /// interpreted as an offset into the PIXEL_UNPACK_BUFFER if that is bound,
-/// or as a raw address if it is not.
+/// or as a raw address if it is not. The same goes for the address of the
+/// slice passed to `compressed_tex_image_2d` and
+/// `compressed_tex_sub_image_2d`.
 ///
 /// When it is a pointer to data, we want to save the data being passed in,
-/// and on replay pass a pointer to the recorded data.
+/// and on replay pass a pointer to the recorded data. The recorder saves only
+/// the bytes GL will actually read, given the unpacking state, dropping any
+/// skipped pixels, rows, and images from the front, so the replay must clear
+/// the skip state when it passes the recorded data.
 ///
 /// When it is an offset, we want to serialize the offset, and pass the
 /// identical offset.
@@ -28,6 +36,13 @@ pub enum TexImageData {
     Offset(usize),
 }
 
+/// A recording-local identifier for a `GLsync` object.
+///
+/// A `GLsync` is an opaque pointer, meaningless outside the process that
+/// recorded it, so the recorder numbers sync objects in the order `fence_sync`
+/// creates them, starting from 1. Zero stands for a null `GLsync`.
+pub type SyncId = u32;
+
 /// An enum representing all possible `Gl` trait method calls.
 ///
 /// This enum has a variant for each method of `Gl` that holds the arguments
@@ -104,8 +119,8 @@ pub enum Call {
     clear { buffer_mask: GLbitfield },
     clear_depth { depth: f64 },
     clear_stencil { s: GLint },
-    get_attrib_location { program: GLuint, name: Var<Str> },
-    get_frag_data_location { program: GLuint, name: Var<Str> },
+    get_attrib_location { program: GLuint, name: Var<Str>, returned: c_int },
+    get_frag_data_location { program: GLuint, name: Var<Str>, returned: c_int },
     get_uniform_location { program: GLuint, name: Var<Str>, returned: c_int },
     get_program_iv { program: GLuint, pname: GLenum, result: Var<Seq<GLint>> },
     uniform_1i { location: GLint, v0: GLint },
@@ -171,4 +186,78 @@ pub enum Call {
     get_tex_image_into_buffer { target: GLenum, level: GLint, format: GLenum, ty: GLenum, output: Var<Seq<u8>> },
     copy_image_sub_data { src_name: GLuint, src_target: GLenum, src_level: GLint, src_x: GLint, src_y: GLint, src_z: GLint, dst_name: GLuint, dst_target: GLenum, dst_level: GLint, dst_x: GLint, dst_y: GLint, dst_z: GLint, src_width: GLsizei, src_height: GLsizei, src_depth: GLsizei },
     generate_mipmap { target: GLenum },
+    stencil_mask { mask: GLuint },
+    stencil_mask_separate { face: GLenum, mask: GLuint },
+    stencil_func { func: GLenum, ref_: GLint, mask: GLuint },
+    stencil_func_separate { face: GLenum, func: GLenum, ref_: GLint, mask: GLuint },
+    stencil_op { sfail: GLenum, dpfail: GLenum, dppass: GLenum },
+    stencil_op_separate { face: GLenum, sfail: GLenum, dpfail: GLenum, dppass: GLenum },
+    draw_arrays { mode: GLenum, first: GLint, count: GLsizei },
+    draw_arrays_instanced { mode: GLenum, first: GLint, count: GLsizei, primcount: GLsizei },
+    draw_elements { mode: GLenum, count: GLsizei, element_type: GLenum, indices_offset: GLuint },
+    map_buffer { target: GLenum, access: GLbitfield },
+    map_buffer_range { target: GLenum, offset: GLintptr, length: GLsizeiptr, access: GLbitfield },
+    unmap_buffer { target: GLenum, offset: GLintptr, length: GLsizeiptr, access: GLbitfield, data: Option<Var<Seq<u8>>>, untracked: bool, returned: GLboolean },
+    buffer_sub_data_untyped { target: GLenum, offset: isize, size_data: Var<Seq<u8>> },
+    compressed_tex_image_2d { target: GLenum, level: GLint, internal_format: GLenum, width: GLsizei, height: GLsizei, border: GLint, data: TexImageData, image_size: usize },
+    compressed_tex_sub_image_2d { target: GLenum, level: GLint, xoffset: GLint, yoffset: GLint, width: GLsizei, height: GLsizei, format: GLenum, data: TexImageData, image_size: usize },
+    copy_tex_image_2d { target: GLenum, level: GLint, internal_format: GLenum, x: GLint, y: GLint, width: GLsizei, height: GLsizei, border: GLint },
+    copy_tex_sub_image_2d { target: GLenum, level: GLint, xoffset: GLint, yoffset: GLint, x: GLint, y: GLint, width: GLsizei, height: GLsizei },
+    copy_tex_sub_image_3d { target: GLenum, level: GLint, xoffset: GLint, yoffset: GLint, zoffset: GLint, x: GLint, y: GLint, width: GLsizei, height: GLsizei },
+    bind_buffer_base { target: GLenum, index: GLuint, buffer: GLuint },
+    bind_buffer_range { target: GLenum, index: GLuint, buffer: GLuint, offset: GLintptr, size: GLsizeiptr },
+    uniform_block_binding { program: GLuint, uniform_block_index: GLuint, uniform_block_binding: GLuint },
+    get_uniform_block_index { program: GLuint, name: Var<Str>, returned: GLuint },
+    get_uniform_indices { program: GLuint, names: Var<Seq<Str>>, returned: Var<Seq<GLuint>> },
+    get_active_uniform_block_i { program: GLuint, index: GLuint, pname: GLenum, returned: GLint },
+    get_active_uniform_block_iv { program: GLuint, index: GLuint, pname: GLenum, returned: Var<Seq<GLint>> },
+    get_active_uniform_block_name { program: GLuint, index: GLuint, returned: Var<Str> },
+    get_active_attrib { program: GLuint, index: GLuint, returned: (i32, u32, Var<Str>) },
+    get_active_uniform { program: GLuint, index: GLuint, returned: (i32, u32, Var<Str>) },
+    get_active_uniforms_iv { program: GLuint, indices: Var<Seq<GLuint>>, pname: GLenum, returned: Var<Seq<GLint>> },
+    get_program_info_log { program: GLuint, returned: Var<Str> },
+    get_shader_precision_format { shader_type: GLuint, precision_type: GLuint, returned: (GLint, GLint, GLint) },
+    get_program_binary { program: GLuint, returned: (Var<Seq<u8>>, GLenum) },
+    program_binary { program: GLuint, format: GLenum, binary: Var<Seq<u8>> },
+    program_parameter_i { program: GLuint, pname: GLenum, value: GLint },
+    get_vertex_attrib_iv { index: GLuint, pname: GLenum, result: Var<Seq<GLint>> },
+    get_vertex_attrib_fv { index: GLuint, pname: GLenum, result: Var<Seq<GLfloat>> },
+    get_vertex_attrib_pointer_v { index: GLuint, pname: GLenum, returned: GLsizeiptr },
+    get_buffer_parameter_iv { target: GLuint, pname: GLenum, returned: GLint },
+    get_framebuffer_attachment_parameter_iv { target: GLenum, attachment: GLenum, pname: GLenum, returned: GLint },
+    get_renderbuffer_parameter_iv { target: GLenum, pname: GLenum, returned: GLint },
+    get_tex_parameter_iv { target: GLenum, name: GLenum, returned: GLint },
+    get_tex_parameter_fv { target: GLenum, name: GLenum, returned: GLfloat },
+    insert_event_marker_ext { message: Var<Str> },
+    push_group_marker_ext { message: Var<Str> },
+    pop_group_marker_ext {},
+    debug_message_insert_khr { source: GLenum, type_: GLenum, id: GLuint, severity: GLenum, message: Var<Str> },
+    push_debug_group_khr { source: GLenum, id: GLuint, message: Var<Str> },
+    pop_debug_group_khr {},
+    fence_sync { condition: GLenum, flags: GLbitfield, returned: SyncId },
+    client_wait_sync { sync: SyncId, flags: GLbitfield, timeout: GLuint64 },
+    wait_sync { sync: SyncId, flags: GLbitfield, timeout: GLuint64 },
+    delete_sync { sync: SyncId },
+    texture_range_apple { target: GLenum, length: usize },
+    gen_fences_apple { n: GLsizei, returned: Var<Seq<GLuint>> },
+    delete_fences_apple { fences: Var<Seq<GLuint>> },
+    set_fence_apple { fence: GLuint },
+    finish_fence_apple { fence: GLuint },
+    test_fence_apple { fence: GLuint },
+    test_object_apple { object: GLenum, name: GLuint, returned: GLboolean },
+    finish_object_apple { object: GLenum, name: GLuint },
+    egl_image_target_texture2d_oes { target: GLenum, pixels: Option<Var<PixelsForm>> },
+    egl_image_target_renderbuffer_storage_oes { target: GLenum, pixels: Option<Var<PixelsForm>> },
+    copy_texture_chromium { source_id: GLuint, source_level: GLint, dest_target: GLenum, dest_id: GLuint, dest_level: GLint, internal_format: GLint, dest_type: GLenum, unpack_flip_y: GLboolean, unpack_premultiply_alpha: GLboolean, unpack_unmultiply_alpha: GLboolean },
+    copy_sub_texture_chromium { source_id: GLuint, source_level: GLint, dest_target: GLenum, dest_id: GLuint, dest_level: GLint, x_offset: GLint, y_offset: GLint, x: GLint, y: GLint, width: GLsizei, height: GLsizei, unpack_flip_y: GLboolean, unpack_premultiply_alpha: GLboolean, unpack_unmultiply_alpha: GLboolean },
+    copy_texture_3d_angle { source_id: GLuint, source_level: GLint, dest_target: GLenum, dest_id: GLuint, dest_level: GLint, internal_format: GLint, dest_type: GLenum, unpack_flip_y: GLboolean, unpack_premultiply_alpha: GLboolean, unpack_unmultiply_alpha: GLboolean },
+    copy_sub_texture_3d_angle { source_id: GLuint, source_level: GLint, dest_target: GLenum, dest_id: GLuint, dest_level: GLint, x_offset: GLint, y_offset: GLint, z_offset: GLint, x: GLint, y: GLint, z: GLint, width: GLsizei, height: GLsizei, depth: GLsizei, unpack_flip_y: GLboolean, unpack_premultiply_alpha: GLboolean, unpack_unmultiply_alpha: GLboolean },
+    bind_frag_data_location_indexed { program: GLuint, color_number: GLuint, index: GLuint, name: Var<Str> },
+    get_frag_data_index { program: GLuint, name: Var<Str>, returned: GLint },
+    tex_sub_image_2d { target: GLenum, level: GLint, xoffset: GLint, yoffset: GLint, width: GLsizei, height: GLsizei, format: GLenum, ty: GLenum, data: Var<Seq<u8>> },
+    draw_buffers { bufs: Var<Seq<GLenum>> },
+    tex_buffer { target: GLenum, internal_format: GLenum, buffer: GLuint },
+    vertex_attrib_4f { index: GLuint, x: GLfloat, y: GLfloat, z: GLfloat, w: GLfloat },
+    vertex_attrib_pointer_f32 { index: GLuint, size: GLint, normalized: bool, stride: GLsizei, offset: GLuint },
+    validate_program { program: GLuint, status: GLint },
 }
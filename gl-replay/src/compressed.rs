@@ -0,0 +1,114 @@
+//! Information about compressed texture formats.
+//!
+//! We don't decode compressed texture data, but it's still helpful for tools
+//! like `dump-images` to be able to say what a compressed upload contains.
+
+use gleam::gl::GLenum;
+
+/// A block-compressed texture format.
+#[derive(Copy, Clone, Debug)]
+pub struct CompressedFormat {
+    /// The OpenGL internal format value identifying this format.
+    pub internal_format: GLenum,
+
+    /// The name of the OpenGL internal format, without the `GL_` prefix.
+    pub name: &'static str,
+
+    /// The width and height of each compressed block, in pixels.
+    pub block_width: usize,
+    pub block_height: usize,
+
+    /// The size of each compressed block, in bytes.
+    pub block_bytes: usize,
+}
+
+macro_rules! formats {
+    ( $( $value:literal $name:ident $block_width:literal x $block_height:literal, $block_bytes:literal; )* ) => {
+        &[
+            $(
+                CompressedFormat {
+                    internal_format: $value,
+                    name: stringify!($name),
+                    block_width: $block_width,
+                    block_height: $block_height,
+                    block_bytes: $block_bytes,
+                },
+            )*
+        ]
+    }
+}
+
+// We spell out the enum values here, rather than using `gleam`'s constants,
+// since `gleam` doesn't define all the extensions' formats.
+static FORMATS: &[CompressedFormat] = formats! {
+    0x83F0 COMPRESSED_RGB_S3TC_DXT1_EXT 4 x 4, 8;
+    0x83F1 COMPRESSED_RGBA_S3TC_DXT1_EXT 4 x 4, 8;
+    0x83F2 COMPRESSED_RGBA_S3TC_DXT3_EXT 4 x 4, 16;
+    0x83F3 COMPRESSED_RGBA_S3TC_DXT5_EXT 4 x 4, 16;
+    0x8D64 ETC1_RGB8_OES 4 x 4, 8;
+    0x8DBB COMPRESSED_RED_RGTC1 4 x 4, 8;
+    0x8DBC COMPRESSED_SIGNED_RED_RGTC1 4 x 4, 8;
+    0x8DBD COMPRESSED_RG_RGTC2 4 x 4, 16;
+    0x8DBE COMPRESSED_SIGNED_RG_RGTC2 4 x 4, 16;
+    0x8E8C COMPRESSED_RGBA_BPTC_UNORM 4 x 4, 16;
+    0x8E8D COMPRESSED_SRGB_ALPHA_BPTC_UNORM 4 x 4, 16;
+    0x8E8E COMPRESSED_RGB_BPTC_SIGNED_FLOAT 4 x 4, 16;
+    0x8E8F COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT 4 x 4, 16;
+    0x9270 COMPRESSED_R11_EAC 4 x 4, 8;
+    0x9271 COMPRESSED_SIGNED_R11_EAC 4 x 4, 8;
+    0x9272 COMPRESSED_RG11_EAC 4 x 4, 16;
+    0x9273 COMPRESSED_SIGNED_RG11_EAC 4 x 4, 16;
+    0x9274 COMPRESSED_RGB8_ETC2 4 x 4, 8;
+    0x9275 COMPRESSED_SRGB8_ETC2 4 x 4, 8;
+    0x9276 COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2 4 x 4, 8;
+    0x9277 COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2 4 x 4, 8;
+    0x9278 COMPRESSED_RGBA8_ETC2_EAC 4 x 4, 16;
+    0x9279 COMPRESSED_SRGB8_ALPHA8_ETC2_EAC 4 x 4, 16;
+    0x93B0 COMPRESSED_RGBA_ASTC_4x4_KHR 4 x 4, 16;
+    0x93B1 COMPRESSED_RGBA_ASTC_5x4_KHR 5 x 4, 16;
+    0x93B2 COMPRESSED_RGBA_ASTC_5x5_KHR 5 x 5, 16;
+    0x93B3 COMPRESSED_RGBA_ASTC_6x5_KHR 6 x 5, 16;
+    0x93B4 COMPRESSED_RGBA_ASTC_6x6_KHR 6 x 6, 16;
+    0x93B5 COMPRESSED_RGBA_ASTC_8x5_KHR 8 x 5, 16;
+    0x93B6 COMPRESSED_RGBA_ASTC_8x6_KHR 8 x 6, 16;
+    0x93B7 COMPRESSED_RGBA_ASTC_8x8_KHR 8 x 8, 16;
+    0x93B8 COMPRESSED_RGBA_ASTC_10x5_KHR 10 x 5, 16;
+    0x93B9 COMPRESSED_RGBA_ASTC_10x6_KHR 10 x 6, 16;
+    0x93BA COMPRESSED_RGBA_ASTC_10x8_KHR 10 x 8, 16;
+    0x93BB COMPRESSED_RGBA_ASTC_10x10_KHR 10 x 10, 16;
+    0x93BC COMPRESSED_RGBA_ASTC_12x10_KHR 12 x 10, 16;
+    0x93BD COMPRESSED_RGBA_ASTC_12x12_KHR 12 x 12, 16;
+};
+
+impl CompressedFormat {
+    /// Return the compressed format whose OpenGL internal format is
+    /// `internal_format`, if we know it.
+    pub fn from_gl(internal_format: GLenum) -> Option<&'static CompressedFormat> {
+        FORMATS
+            .iter()
+            .find(|format| format.internal_format == internal_format)
+    }
+
+    /// Return the number of bytes needed to hold a `width` by `height` image in
+    /// this format. Partial blocks at the right and bottom edges still occupy
+    /// a full block.
+    pub fn image_size(&self, width: usize, height: usize) -> usize {
+        let blocks_wide = (width + self.block_width - 1) / self.block_width;
+        let blocks_high = (height + self.block_height - 1) / self.block_height;
+        blocks_wide * blocks_high * self.block_bytes
+    }
+}
+
+#[test]
+fn test_image_size() {
+    let dxt1 = CompressedFormat::from_gl(0x83F1).unwrap();
+    assert_eq!(dxt1.image_size(4, 4), 8);
+    assert_eq!(dxt1.image_size(5, 4), 16);
+    assert_eq!(dxt1.image_size(1, 1), 8);
+
+    let astc = CompressedFormat::from_gl(0x93B8).unwrap();
+    assert_eq!((astc.block_width, astc.block_height), (10, 5));
+    assert_eq!(astc.image_size(20, 11), 2 * 3 * 16);
+
+    assert!(CompressedFormat::from_gl(gleam::gl::RGBA).is_none());
+}
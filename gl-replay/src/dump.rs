@@ -0,0 +1,90 @@
+//! Helpers for printing recorded calls in a readable form.
+
+use crate::call::Call;
+use crate::replay;
+
+/// The nesting of debug groups and group markers at some point in a recording.
+///
+/// Calls between matching pushes and pops are indented by their nesting depth.
+#[derive(Default)]
+pub struct GroupNesting {
+    depth: usize,
+}
+
+impl GroupNesting {
+    /// Return the indentation for `call`, and account for any group it pushes
+    /// or pops.
+    ///
+    /// A push and its matching pop are indented at the same depth as the calls
+    /// surrounding the group.
+    pub fn indent(&mut self, call: &Call) -> String {
+        if is_group_pop(call) {
+            self.depth = self.depth.saturating_sub(1);
+        }
+        let indent = self.current_indent();
+        if is_group_push(call) {
+            self.depth += 1;
+        }
+        indent
+    }
+
+    /// Return the indentation for something other than a `Gl` call at the
+    /// current depth, like an embedder's own recorded events.
+    pub fn current_indent(&self) -> String {
+        "  ".repeat(self.depth)
+    }
+}
+
+fn is_group_push(call: &Call) -> bool {
+    matches!(
+        call,
+        Call::push_group_marker_ext { .. } | Call::push_debug_group_khr { .. }
+    )
+}
+
+fn is_group_pop(call: &Call) -> bool {
+    matches!(
+        call,
+        Call::pop_group_marker_ext {} | Call::pop_debug_group_khr {}
+    )
+}
+
+/// If `call` is a debug marker, return a description of it, including its
+/// message, drawn from `variable`.
+pub fn describe_marker(call: &Call, variable: &[u8]) -> Option<String> {
+    use Call::*;
+    let message = |message| -> &str { replay::get_parameter(message, variable) };
+    match *call {
+        insert_event_marker_ext { message: m } => {
+            Some(format!("insert_event_marker_ext: {:?}", message(m)))
+        }
+        push_group_marker_ext { message: m } => {
+            Some(format!("push_group_marker_ext: {:?}", message(m)))
+        }
+        debug_message_insert_khr {
+            source,
+            type_,
+            id,
+            severity,
+            message: m,
+        } => Some(format!(
+            "debug_message_insert_khr: source 0x{:x}, type 0x{:x}, id {}, severity 0x{:x}: {:?}",
+            source,
+            type_,
+            id,
+            severity,
+            message(m)
+        )),
+        push_debug_group_khr {
+            source,
+            id,
+            message: m,
+        } => Some(format!(
+            "push_debug_group_khr: source 0x{:x}, id {}: {:?}",
+            source,
+            id,
+            message(m)
+        )),
+        _ => None,
+    }
+}
@@ -12,7 +12,10 @@
 //!
 //! Then, the `replay` function takes a reference to the contents of a saved
 //! recording, and performs the same series of calls on a new `Gl`
-//! implementation you provide.
+//! implementation you provide. To control how the calls are replayed, create
+//! a [`Replayer`] with the options you need and use its `replay` method. If a
+//! call's results differ from those recorded, replay returns a `ReplayError`
+//! describing the divergence.
 //!
 //! On the filesystem, a recording is actually a directory, containing a number
 //! of files. The `calls` file holds an array of fixed-size entries describing
@@ -22,8 +25,8 @@
 //! You can combine this crates' recordings with other events of your choice.
 //! The `Recorder` type can use any implementation of the `Serializer` trait to
 //! record calls, so you can provide your own `Serializer` implementation that
-//! combines the `Gl` calls with your own data. Then, at replay time, there is a
-//! `replay_one` function that replays a single `Gl` call, which you can use
+//! combines the `Gl` calls with your own data. Then, at replay time, the
+//! `Replayer::replay_one` method replays a single `Gl` call, which you can use
 //! from your own replay loop. This crate also exposes simple `Serializer` and
 //! `Deserializer` traits which you can use if they meet your needs.
 //!
@@ -38,7 +41,10 @@ use std::io;
 use std::path::Path;
 
 mod call;
-pub use call::{Call, TexImageData};
+pub use call::{Call, SyncId, TexImageData};
+
+pub mod compressed;
+pub mod dump;
 
 mod file_stream;
 pub use file_stream::{FileRecording, FileStream};
@@ -57,7 +63,7 @@ pub mod pixels;
 pub mod rle;
 
 pub mod replay;
-pub use replay::{replay, replay_one};
+pub use replay::{replay, replay_one, Namespace, QueryCheck, ReplayError, Replayer};
 
 /// A `gleam::Gl` implementation that records calls to files.
 type FileRecorder<G> = Recorder<G, FileStream<Call>>;
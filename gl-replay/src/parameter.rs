@@ -70,6 +70,14 @@ impl Parameter for str {
     }
 }
 
+impl Parameter for String {
+    type Form = Var<Str>;
+
+    fn to_call<S: MarkedWrite>(&self, stream: &mut S) -> io::Result<Self::Form> {
+        self.as_str().to_call(stream)
+    }
+}
+
 impl Parameter for pixels::Pixels<'_> {
     type Form = Var<pixels::PixelsForm>;
     fn to_call<S: MarkedWrite>(&self, stream: &mut S) -> io::Result<Self::Form> {
@@ -106,3 +114,25 @@ impl<T: Parameter> Parameter for Option<T> {
         self.as_ref().map(|param| param.to_call(stream)).transpose() // from `Option<Result>` to `Result<Option>`
     }
 }
+
+/// Tuples are passed as a tuple of their elements' forms. This is mostly
+/// useful for recording methods that return tuples.
+macro_rules! tuple_parameters {
+    ( $( ( $( $type:ident $value:ident ),* ) )* ) => {
+        $(
+            impl< $( $type: Parameter ),* > Parameter for ( $( $type, )* ) {
+                type Form = ( $( $type::Form, )* );
+
+                fn to_call<S: MarkedWrite>(&self, stream: &mut S) -> io::Result<Self::Form> {
+                    let ( $( $value, )* ) = self;
+                    Ok(( $( $value.to_call(stream)?, )* ))
+                }
+            }
+        )*
+    }
+}
+
+tuple_parameters! {
+    (A a, B b)
+    (A a, B b, C c)
+}
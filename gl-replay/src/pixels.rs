@@ -55,9 +55,10 @@ pub struct Pixels<'a> {
 /// -   If each pixel is a single byte, then data is written as with
 ///     `rle::write_rle_u8`.
 ///
-/// -   If each pixel is a four-byte value, the stream is padded to a four-byte
-///     alignment boundary, and then written as by `rle::write_rle_u32`. The
-///     padding is not included in the compressed length.
+/// -   If each pixel is a multiple of four bytes long, like RGBA `FLOAT`
+///     pixels, the stream is padded to a four-byte alignment boundary, and
+///     then written as by `rle::write_rle_u32`. The padding is not included
+///     in the compressed length.
 ///
 /// Other formats aren't yet supported, since we don't use them, but the `rle`
 /// module has generic functions that should make it easy.
@@ -85,7 +86,7 @@ impl var::Serialize for Pixels<'_> {
             1 => {
                 rle::write_u8(&mut compressed, &self.bytes)?;
             }
-            4 => {
+            n if n % 4 == 0 => {
                 assert!(self.bytes.len() % mem::align_of::<u32>() == 0);
                 let slice = unsafe {
                     std::slice::from_raw_parts(
@@ -99,7 +100,7 @@ impl var::Serialize for Pixels<'_> {
         }
 
         leb128::write::unsigned(stream, compressed.len() as u64)?;
-        if bytes_per_pixel == 4 {
+        if bytes_per_pixel % 4 == 0 {
             stream.align_for::<u32>()?;
         }
         stream.write_all(&compressed)?;
@@ -118,11 +119,11 @@ impl<'b> var::DeserializeAs<'b, Pixels<'static>> for PixelsForm {
         let compressed_length = leb128::read::unsigned(buf)? as usize;
 
         let bytes_per_pixel = gl::calculate_bytes_per_pixel(format, pixel_type);
-        assert_eq!(compressed_length % bytes_per_pixel, 0);
 
         let bytes = match bytes_per_pixel {
             1 => rle::read_u8(buf)?,
-            4 => {
+            n if n % 4 == 0 => {
+                assert_eq!(compressed_length % 4, 0);
                 let mut words: &[u32] = var::borrow_aligned_slice(buf, compressed_length / 4)?;
                 rle::read_u32(&mut words)?
             }
@@ -143,6 +144,14 @@ impl<'b> var::DeserializeAs<'b, Pixels<'static>> for PixelsForm {
 }
 
 impl Pixels<'_> {
+    /// Return true if `write_image` can save these pixels: a single layer of
+    /// `RGBA` or `BGRA` `UNSIGNED_BYTE` pixels.
+    pub fn can_write_image(&self) -> bool {
+        self.depth == 1 &&
+            self.pixel_type == gl::UNSIGNED_BYTE &&
+            (self.format == gl::RGBA || self.format == gl::BGRA)
+    }
+
     pub fn write_image<P: AsRef<path::Path>>(&self, path: P) {
         if self.depth != 1 {
             eprintln!("Warning: skipping deep image '{}'",
@@ -1,7 +1,16 @@
 //! Implementation of `Gl` trait for `Recorder`.
 
+use gleam::gl::{
+    GLbitfield, GLeglImageOES, GLintptr, GLsizei, GLsizeiptr, GLsync, GLuint, MAP_WRITE_BIT,
+    SYNC_GPU_COMMANDS_COMPLETE,
+};
+use std::collections::HashMap;
+use std::os::raw::c_void;
 use std::sync;
 
+use crate::call::{Call, SyncId};
+use crate::var::CallStream;
+
 mod impl_gl;
 
 /// An implementation of `gleam::Gl` that records method calls for later replay.
@@ -17,6 +26,61 @@ pub struct Recorder<G, Cs> {
     call_stream: sync::Mutex<Cs>,
 
     fingerprinter: Option<fn(&G, &mut Cs)>,
+
+    /// A function returning the width and height of an EGLImage, which OpenGL
+    /// provides no direct way to query.
+    egl_image_size: Option<fn(&G, GLeglImageOES) -> (GLsizei, GLsizei)>,
+
+    /// Buffers the application currently has mapped, indexed by buffer name.
+    ///
+    /// The application may map a buffer, bind a different one to the same
+    /// target, and map that too, so the target alone doesn't identify a
+    /// mapping.
+    mapped_buffers: sync::Mutex<HashMap<GLuint, MappedBuffer>>,
+
+    /// The ids we've assigned to live sync objects.
+    sync_ids: sync::Mutex<SyncIds>,
+}
+
+/// Recording-local ids for `GLsync` objects.
+#[derive(Default)]
+struct SyncIds {
+    /// The id of each live sync object, indexed by its `GLsync` address.
+    ids: HashMap<usize, SyncId>,
+
+    /// The most recently assigned id.
+    last: SyncId,
+}
+
+/// A buffer mapping we've handed out to the application.
+///
+/// When the application unmaps the buffer, we need to record whatever it wrote
+/// to the mapping, so we remember where it is and how it was mapped.
+struct MappedBuffer {
+    /// The address of the mapping, as returned by the inner `Gl`.
+    address: usize,
+
+    /// The offset and length of the mapped range within the buffer.
+    offset: GLintptr,
+    length: GLsizeiptr,
+
+    /// The access bits the buffer was mapped with, in the form expected by
+    /// `map_buffer_range`.
+    access: GLbitfield,
+}
+
+impl MappedBuffer {
+    /// Return a copy of the mapping's contents, if the application was
+    /// permitted to write to it. Read-only mappings have nothing to record.
+    ///
+    /// Safety: the mapping must not have been unmapped yet.
+    unsafe fn written_bytes(&self) -> Option<Vec<u8>> {
+        if self.access & MAP_WRITE_BIT == 0 {
+            return None;
+        }
+        let bytes = std::slice::from_raw_parts(self.address as *const u8, self.length as usize);
+        Some(bytes.to_owned())
+    }
 }
 
 impl<G, Cs> Recorder<G, Cs> {
@@ -24,7 +88,10 @@ impl<G, Cs> Recorder<G, Cs> {
         Recorder {
             inner_gl,
             call_stream: sync::Mutex::new(call_stream),
-            fingerprinter: None
+            fingerprinter: None,
+            egl_image_size: None,
+            mapped_buffers: sync::Mutex::new(HashMap::new()),
+            sync_ids: sync::Mutex::new(SyncIds::default()),
         }
     }
 
@@ -35,6 +102,24 @@ impl<G, Cs> Recorder<G, Cs> {
         }
     }
 
+    /// Use `egl_image_size` to find the dimensions of EGLImages.
+    ///
+    /// An EGLImage has no meaning outside the process that created it, so when
+    /// the application makes a texture from one, the recorder reads back its
+    /// contents and records those instead. To do that, it needs to know the
+    /// image's size. Without this function, the recorder makes a temporary
+    /// renderbuffer from the image and asks for its size, which needs support
+    /// for EGLImage-backed renderbuffers.
+    pub fn with_egl_image_size(
+        self,
+        egl_image_size: fn(&G, GLeglImageOES) -> (GLsizei, GLsizei),
+    ) -> Self {
+        Recorder {
+            egl_image_size: Some(egl_image_size),
+            .. self
+        }
+    }
+
     pub fn inner_gl(&self) -> &G {
         &self.inner_gl
     }
@@ -42,4 +127,79 @@ impl<G, Cs> Recorder<G, Cs> {
     pub fn lock_call_stream(&self) -> sync::MutexGuard<Cs> {
         self.call_stream.lock().unwrap()
     }
+
+    /// Remember that `address` is a mapping of `buffer`, so that we can record
+    /// what the application writes to it when it's unmapped.
+    fn track_mapping(
+        &self,
+        buffer: GLuint,
+        address: *mut c_void,
+        offset: GLintptr,
+        length: GLsizeiptr,
+        access: GLbitfield,
+    ) {
+        // If the mapping failed, there's nothing for the application to write to.
+        if address.is_null() {
+            return;
+        }
+        let mapping = MappedBuffer {
+            address: address as usize,
+            offset,
+            length,
+            access,
+        };
+        self.mapped_buffers.lock().unwrap().insert(buffer, mapping);
+    }
+
+    /// Assign a new id to the sync object `sync`, just created by the inner `Gl`.
+    fn new_sync_id(&self, sync: GLsync) -> SyncId {
+        if sync.is_null() {
+            return 0;
+        }
+        let mut sync_ids = self.sync_ids.lock().unwrap();
+        sync_ids.last += 1;
+        let id = sync_ids.last;
+        sync_ids.ids.insert(sync as usize, id);
+        id
+    }
+
+    /// Return the id we assigned to `sync`.
+    ///
+    /// If `sync` was created before recording began, or by another context,
+    /// we have never seen it, so we assign it a fresh id, and record a
+    /// stand-in `fence_sync` call to create it on replay. A fence created at
+    /// this point is signaled no earlier than the original, so waiting for it
+    /// still waits for everything the application meant to wait for.
+    fn sync_id(&self, sync: GLsync, call_stream: &mut Cs) -> SyncId
+    where
+        Cs: CallStream<Call>,
+    {
+        if sync.is_null() {
+            return 0;
+        }
+        if let Some(&id) = self.sync_ids.lock().unwrap().ids.get(&(sync as usize)) {
+            return id;
+        }
+        let id = self.new_sync_id(sync);
+        let stand_in = Call::fence_sync {
+            condition: SYNC_GPU_COMMANDS_COMPLETE,
+            flags: 0,
+            returned: id,
+        };
+        call_stream
+            .write_call(stand_in)
+            .expect("gl-replay serialization failure");
+        id
+    }
+
+    /// Return the id we assigned to `sync`, and forget it, since the
+    /// application is deleting it.
+    fn forget_sync_id(&self, sync: GLsync, call_stream: &mut Cs) -> SyncId
+    where
+        Cs: CallStream<Call>,
+    {
+        let id = self.sync_id(sync, call_stream);
+        self.sync_ids.lock().unwrap().ids.remove(&(sync as usize));
+        id
+    }
 }
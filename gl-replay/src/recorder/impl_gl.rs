@@ -1,10 +1,12 @@
 //! Implementation of the `gleam::Gl` trait for `Recorder`.
 
 use gleam::gl::*;
+use std::ops::Range;
 use std::os::raw::{c_int, c_void};
 
-use super::Recorder;
+use super::{MappedBuffer, Recorder};
 use crate::call::{Call, TexImageData};
+use crate::form::{Seq, Var};
 use crate::pixels;
 use crate::var::CallStream;
 use crate::Parameter;
@@ -15,12 +17,23 @@ macro_rules! check {
     };
 }
 
+/// Return true if there is a buffer bound to PIXEL_UNPACK_BUFFER, meaning that
+/// texture upload methods' data pointers are actually offsets into that.
+fn pixel_unpack_buffer_bound<G: gleam::gl::Gl>(inner_gl: &G) -> bool {
+    let mut bound_buffer = 0;
+    unsafe {
+        inner_gl.get_integer_v(gleam::gl::PIXEL_UNPACK_BUFFER_BINDING,
+                               std::slice::from_mut(&mut bound_buffer));
+    }
+    bound_buffer != 0
+}
+
 fn tex_image_data_to_call<G, Cs>(
     inner_gl: &G,
     call_stream: &mut Cs,
     width: GLsizei,
     height: GLsizei,
-    depth: GLsizei,
+    depth: Option<GLsizei>,
     format: GLenum,
     ty: GLenum,
     offset: usize,
@@ -31,31 +44,267 @@ where
 {
     // If there is a buffer bound to PIXEL_UNPACK_BUFFER, then `offset` is an
     // offset; otherwise, it's an address.
-    let mut bound_buffer = 0;
-    unsafe {
-        inner_gl.get_integer_v(gleam::gl::PIXEL_UNPACK_BUFFER_BINDING,
-                               std::slice::from_mut(&mut bound_buffer));
-    }
-    if bound_buffer != 0 {
+    if pixel_unpack_buffer_bound(inner_gl) {
         TexImageData::Offset(offset)
     } else {
-        let mut unpack_row_length = 0;
-        unsafe {
-            inner_gl.get_integer_v(gleam::gl::UNPACK_ROW_LENGTH,
-                                   std::slice::from_mut(&mut unpack_row_length));
-        }
-        let actual_width = if unpack_row_length != 0 {
-            assert!(width <= unpack_row_length);
-            unpack_row_length
-        } else {
-            width
+        let span = client_unpack_span(inner_gl, width, height, depth, format, ty);
+        let slice = unsafe {
+            std::slice::from_raw_parts((offset + span.start) as *const u8, span.len())
         };
-        let length = gleam::gl::calculate_length(actual_width, height, depth, format, ty);
-        let slice = unsafe { std::slice::from_raw_parts(offset as *const u8, length) };
         TexImageData::Buf(check!(slice.to_call(call_stream)))
     }
 }
 
+/// Record the portion of `data` that a texture upload from client memory
+/// actually reads.
+///
+/// See `client_unpack_span` for details.
+fn client_data_to_call<G, Cs>(
+    inner_gl: &G,
+    call_stream: &mut Cs,
+    data: &[u8],
+    width: GLsizei,
+    height: GLsizei,
+    depth: Option<GLsizei>,
+    format: GLenum,
+    ty: GLenum,
+) -> Var<Seq<u8>>
+where
+    G: gleam::gl::Gl,
+    Cs: CallStream<Call>,
+{
+    let span = client_unpack_span(inner_gl, width, height, depth, format, ty);
+    assert!(span.end <= data.len(),
+            "gl-replay: texture upload reads past the end of its data slice");
+    check!(data[span].to_call(call_stream))
+}
+
+/// Pixel storage state governing how texture uploads read client memory.
+///
+/// Zero values for `row_length` and `image_height` mean "use the width and
+/// height of the upload", as in GL.
+#[derive(Copy, Clone, Debug, Default)]
+struct UnpackState {
+    row_length: usize,
+    image_height: usize,
+    skip_pixels: usize,
+    skip_rows: usize,
+    skip_images: usize,
+    alignment: usize,
+}
+
+impl UnpackState {
+    /// Return the range of bytes, relative to an upload's data pointer, that
+    /// GL reads for an upload of the given size.
+    fn span(&self, width: usize, height: usize, depth: usize, pixel_size: usize) -> Range<usize> {
+        if width == 0 || height == 0 || depth == 0 {
+            return 0..0;
+        }
+        let row_length = if self.row_length == 0 { width } else { self.row_length };
+        let image_height = if self.image_height == 0 { height } else { self.image_height };
+        let alignment = self.alignment.max(1);
+        let row_stride = (row_length * pixel_size + alignment - 1) / alignment * alignment;
+        let image_stride = row_stride * image_height;
+
+        let start = self.skip_images * image_stride
+            + self.skip_rows * row_stride
+            + self.skip_pixels * pixel_size;
+        let end = start + (depth - 1) * image_stride + (height - 1) * row_stride + width * pixel_size;
+        start..end
+    }
+}
+
+/// Return the range of bytes, relative to its data pointer, that a texture
+/// upload from client memory will read, given the current unpacking state.
+///
+/// Uploads of sub-rectangles from large atlases use the skip and row length
+/// state to pick out just the part they want, so we record exactly the bytes
+/// GL will read. Since the skipped bytes at the front are dropped, the replay
+/// must clear the skip state when passing the recorded data.
+///
+/// Pass `None` for `depth` for two-dimensional uploads, which ignore the
+/// image height and image skip state.
+fn client_unpack_span<G: gleam::gl::Gl>(
+    inner_gl: &G,
+    width: GLsizei,
+    height: GLsizei,
+    depth: Option<GLsizei>,
+    format: GLenum,
+    ty: GLenum,
+) -> Range<usize> {
+    let get = |name| get_integer(inner_gl, name) as usize;
+    let mut state = UnpackState {
+        row_length: get(UNPACK_ROW_LENGTH),
+        skip_pixels: get(UNPACK_SKIP_PIXELS),
+        skip_rows: get(UNPACK_SKIP_ROWS),
+        alignment: get(UNPACK_ALIGNMENT),
+        ..UnpackState::default()
+    };
+    if depth.is_some() {
+        state.image_height = get(UNPACK_IMAGE_HEIGHT);
+        state.skip_images = get(UNPACK_SKIP_IMAGES);
+    }
+    let pixel_size = calculate_length(1, 1, 1, format, ty);
+    state.span(width as usize, height as usize, depth.unwrap_or(1) as usize, pixel_size)
+}
+
+#[test]
+fn test_unpack_span() {
+    let tight = UnpackState { alignment: 1, ..UnpackState::default() };
+    assert_eq!(tight.span(3, 2, 1, 4), 0..24);
+    assert_eq!(tight.span(0, 2, 1, 4), 0..0);
+
+    // Rows of three RGB pixels padded out to a multiple of four bytes.
+    let aligned = UnpackState { alignment: 4, ..UnpackState::default() };
+    assert_eq!(aligned.span(3, 2, 1, 3), 0..21);
+
+    // A 2x2 sub-rectangle at (5, 3) of a 16-pixel-wide atlas.
+    let atlas = UnpackState { row_length: 16, skip_pixels: 5, skip_rows: 3, alignment: 4,
+                              ..UnpackState::default() };
+    assert_eq!(atlas.span(2, 2, 1, 4), 212..284);
+
+    // The second image of a stack of 4x4 images, reading only two layers.
+    let layers = UnpackState { image_height: 4, skip_images: 1, alignment: 4,
+                               ..UnpackState::default() };
+    assert_eq!(layers.span(4, 2, 2, 1), 16..40);
+}
+
+/// Return the `TexImageData` representing the `data` argument to a compressed
+/// texture upload method.
+///
+/// `gleam` takes compressed data as a slice, but if there is a buffer bound to
+/// PIXEL_UNPACK_BUFFER, the slice's address is actually an offset into that,
+/// and we mustn't try to read it.
+fn compressed_data_to_call<G, Cs>(inner_gl: &G, call_stream: &mut Cs, data: &[u8]) -> TexImageData
+where
+    G: gleam::gl::Gl,
+    Cs: CallStream<Call>,
+{
+    if pixel_unpack_buffer_bound(inner_gl) {
+        TexImageData::Offset(data.as_ptr() as usize)
+    } else {
+        TexImageData::Buf(check!(data.to_call(call_stream)))
+    }
+}
+
+fn get_integer<G: gleam::gl::Gl>(inner_gl: &G, name: GLenum) -> GLint {
+    let mut value = 0;
+    unsafe {
+        inner_gl.get_integer_v(name, std::slice::from_mut(&mut value));
+    }
+    value
+}
+
+/// Return the name of the buffer bound to `target`, or `None` if `target` is not
+/// a buffer target we know how to query.
+fn bound_buffer<G: gleam::gl::Gl>(inner_gl: &G, target: GLenum) -> Option<GLuint> {
+    let binding = match target {
+        ARRAY_BUFFER => ARRAY_BUFFER_BINDING,
+        ELEMENT_ARRAY_BUFFER => ELEMENT_ARRAY_BUFFER_BINDING,
+        PIXEL_PACK_BUFFER => PIXEL_PACK_BUFFER_BINDING,
+        PIXEL_UNPACK_BUFFER => PIXEL_UNPACK_BUFFER_BINDING,
+        UNIFORM_BUFFER => UNIFORM_BUFFER_BINDING,
+        TRANSFORM_FEEDBACK_BUFFER => TRANSFORM_FEEDBACK_BUFFER_BINDING,
+        DRAW_INDIRECT_BUFFER => DRAW_INDIRECT_BUFFER_BINDING,
+        DISPATCH_INDIRECT_BUFFER => DISPATCH_INDIRECT_BUFFER_BINDING,
+        SHADER_STORAGE_BUFFER => SHADER_STORAGE_BUFFER_BINDING,
+        ATOMIC_COUNTER_BUFFER => ATOMIC_COUNTER_BUFFER_BINDING,
+        QUERY_BUFFER => QUERY_BUFFER_BINDING,
+        // For these targets, the target itself is the binding's name.
+        COPY_READ_BUFFER | COPY_WRITE_BUFFER | TEXTURE_BUFFER => target,
+        _ => return None,
+    };
+    Some(get_integer(inner_gl, binding) as GLuint)
+}
+
+/// Read back the contents of an image as RGBA pixels.
+///
+/// The `attach` function should attach the image to the color attachment of
+/// the framebuffer bound to `READ_FRAMEBUFFER`. We restore any bindings we
+/// disturb, and none of this is recorded. If the image can't be attached to a
+/// framebuffer, return `None`.
+///
+/// Floating-point images are read back as `FLOAT` pixels, and all others as
+/// `UNSIGNED_BYTE`. (`gleam` can't read back integer formats.)
+fn read_back_image<G, F>(
+    inner_gl: &G,
+    width: GLsizei,
+    height: GLsizei,
+    attach: F,
+) -> Option<pixels::Pixels<'static>>
+where
+    G: gleam::gl::Gl,
+    F: FnOnce(&G),
+{
+    let read_framebuffer = get_integer(inner_gl, READ_FRAMEBUFFER_BINDING) as GLuint;
+    let pack_buffer = get_integer(inner_gl, PIXEL_PACK_BUFFER_BINDING) as GLuint;
+
+    let framebuffer = inner_gl.gen_framebuffers(1)[0];
+    inner_gl.bind_framebuffer(READ_FRAMEBUFFER, framebuffer);
+    attach(inner_gl);
+    let status = inner_gl.check_frame_buffer_status(READ_FRAMEBUFFER);
+    let pixels = if status == FRAMEBUFFER_COMPLETE {
+        let component_type = inner_gl.get_framebuffer_attachment_parameter_iv(
+            READ_FRAMEBUFFER,
+            COLOR_ATTACHMENT0,
+            FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE,
+        ) as GLenum;
+        let pixel_type = if component_type == FLOAT { FLOAT } else { UNSIGNED_BYTE };
+        inner_gl.bind_buffer(PIXEL_PACK_BUFFER, 0);
+        let bytes = inner_gl.read_pixels(0, 0, width, height, RGBA, pixel_type);
+        Some(pixels::Pixels {
+            width: width as usize,
+            height: height as usize,
+            depth: 1,
+            format: RGBA,
+            pixel_type,
+            bytes: bytes.into(),
+        })
+    } else {
+        None
+    };
+
+    inner_gl.bind_buffer(PIXEL_PACK_BUFFER, pack_buffer);
+    inner_gl.bind_framebuffer(READ_FRAMEBUFFER, read_framebuffer);
+    inner_gl.delete_framebuffers(&[framebuffer]);
+
+    pixels
+}
+
+/// Return the width and height of `image`.
+///
+/// OpenGL has no way to ask an EGLImage its size, but it can report the size
+/// of a renderbuffer, so we query a temporary renderbuffer whose storage is
+/// `image`. None of this is recorded.
+fn egl_image_size<G>(inner_gl: &G, image: GLeglImageOES) -> (GLsizei, GLsizei)
+where
+    G: gleam::gl::Gl,
+{
+    let saved_renderbuffer = get_integer(inner_gl, RENDERBUFFER_BINDING) as GLuint;
+    let renderbuffer = inner_gl.gen_renderbuffers(1)[0];
+    inner_gl.bind_renderbuffer(RENDERBUFFER, renderbuffer);
+    inner_gl.egl_image_target_renderbuffer_storage_oes(RENDERBUFFER, image);
+    let width = inner_gl.get_renderbuffer_parameter_iv(RENDERBUFFER, RENDERBUFFER_WIDTH);
+    let height = inner_gl.get_renderbuffer_parameter_iv(RENDERBUFFER, RENDERBUFFER_HEIGHT);
+    inner_gl.bind_renderbuffer(RENDERBUFFER, saved_renderbuffer);
+    inner_gl.delete_renderbuffers(&[renderbuffer]);
+    (width, height)
+}
+
+/// Convert a `map_buffer` access value (`READ_ONLY`, `WRITE_ONLY`, or
+/// `READ_WRITE`) to the equivalent `map_buffer_range` access bits.
+///
+/// Other values are passed through unchanged. The inner `Gl` rejects them, so
+/// the mapping fails and there is nothing to track anyway.
+fn map_buffer_access_bits(access: GLenum) -> GLbitfield {
+    match access {
+        READ_ONLY => MAP_READ_BIT,
+        WRITE_ONLY => MAP_WRITE_BIT,
+        READ_WRITE => MAP_READ_BIT | MAP_WRITE_BIT,
+        _ => access,
+    }
+}
+
 /// General form of call that has no side effects, and hence doesn't need to be
 /// recorded.
 macro_rules! no_side_effect {
@@ -167,11 +416,36 @@ where
         size: GLsizeiptr,
         data: *const GLvoid,
     ) {
-        unimplemented!("buffer_sub_data_untyped");
+        general! {
+            let returned = self.buffer_sub_data_untyped(target, offset, size, data);
+            lock call_stream;
+            {
+                let size_data = unsafe {
+                    std::slice::from_raw_parts(data as *const u8, size as usize)
+                };
+                let call = Call::buffer_sub_data_untyped {
+                    target,
+                    offset,
+                    size_data: check!(size_data.to_call(call_stream)),
+                };
+                check!(call_stream.write_call(call));
+            }
+        }
     }
 
     fn map_buffer(&self, target: GLenum, access: GLbitfield) -> *mut c_void {
-        unimplemented!("map_buffer");
+        general! {
+            let returned = self.map_buffer(target, access);
+            lock call_stream;
+            {
+                if let Some(buffer) = bound_buffer(&self.inner_gl, target) {
+                    let length = self.inner_gl.get_buffer_parameter_iv(target, BUFFER_SIZE);
+                    self.track_mapping(buffer, returned, 0, length as GLsizeiptr,
+                                       map_buffer_access_bits(access));
+                }
+                check!(call_stream.write_call(Call::map_buffer { target, access }));
+            }
+        }
     }
 
     fn map_buffer_range(
@@ -181,15 +455,56 @@ where
         length: GLsizeiptr,
         access: GLbitfield,
     ) -> *mut c_void {
-        unimplemented!("map_buffer_range");
+        general! {
+            let returned = self.map_buffer_range(target, offset, length, access);
+            lock call_stream;
+            {
+                if let Some(buffer) = bound_buffer(&self.inner_gl, target) {
+                    self.track_mapping(buffer, returned, offset, length, access);
+                }
+                check!(call_stream.write_call(Call::map_buffer_range {
+                    target, offset, length, access
+                }));
+            }
+        }
     }
 
     fn unmap_buffer(&self, target: GLenum) -> GLboolean {
-        unimplemented!("unmap_buffer");
+        // Once the inner `Gl` has unmapped the buffer, the mapping's contents
+        // are gone, so we must copy out what the application wrote first.
+        let buffer = bound_buffer(&self.inner_gl, target);
+        let mapping = buffer
+            .and_then(|buffer| self.mapped_buffers.lock().unwrap().remove(&buffer));
+        let data = mapping
+            .as_ref()
+            .and_then(|mapping| unsafe { mapping.written_bytes() });
+        general! {
+            let returned = self.unmap_buffer(target);
+            lock call_stream;
+            {
+                let (offset, length, access) = match mapping {
+                    Some(MappedBuffer { offset, length, access, .. }) => (offset, length, access),
+                    None => (0, 0, 0),
+                };
+                let call = Call::unmap_buffer {
+                    target,
+                    offset,
+                    length,
+                    access,
+                    data: check!(data.to_call(call_stream)),
+                    // If we don't know which buffer `target` refers to, we
+                    // couldn't track its mapping. Say so, rather than looking
+                    // like a mapping nobody wrote to.
+                    untracked: buffer.is_none(),
+                    returned,
+                };
+                check!(call_stream.write_call(call));
+            }
+        }
     }
 
     fn tex_buffer(&self, target: GLenum, internal_format: GLenum, buffer: GLuint) {
-        unimplemented!("tex_buffer");
+        simple!(self.tex_buffer(target, internal_format, buffer))
     }
 
     fn shader_source(&self, shader: GLuint, strings: &[&[u8]]) {
@@ -397,15 +712,15 @@ where
     }
 
     fn get_uniform_block_index(&self, program: GLuint, name: &str) -> GLuint {
-        unimplemented!("get_uniform_block_index");
+        simple_with_return_value!(self.get_uniform_block_index(program, name))
     }
 
     fn get_uniform_indices(&self, program: GLuint, names: &[&str]) -> Vec<GLuint> {
-        unimplemented!("get_uniform_indices");
+        simple_with_return_value!(self.get_uniform_indices(program, names))
     }
 
     fn bind_buffer_base(&self, target: GLenum, index: GLuint, buffer: GLuint) {
-        unimplemented!("bind_buffer_base");
+        simple!(self.bind_buffer_base(target, index, buffer))
     }
 
     fn bind_buffer_range(
@@ -416,7 +731,7 @@ where
         offset: GLintptr,
         size: GLsizeiptr,
     ) {
-        unimplemented!("bind_buffer_range");
+        simple!(self.bind_buffer_range(target, index, buffer, offset, size))
     }
 
     fn uniform_block_binding(
@@ -425,7 +740,7 @@ where
         uniform_block_index: GLuint,
         uniform_block_binding: GLuint,
     ) {
-        unimplemented!("uniform_block_binding");
+        simple!(self.uniform_block_binding(program, uniform_block_index, uniform_block_binding))
     }
 
     fn bind_buffer(&self, target: GLenum, buffer: GLuint) {
@@ -453,7 +768,7 @@ where
     }
 
     fn draw_buffers(&self, bufs: &[GLenum]) {
-        unimplemented!("draw_buffers");
+        simple!(self.draw_buffers(bufs))
     }
 
     fn tex_image_2d(
@@ -468,17 +783,22 @@ where
         ty: GLenum,
         opt_data: Option<&[u8]>,
     ) {
-        simple!(self.tex_image_2d(
-            target,
-            level,
-            internal_format,
-            width,
-            height,
-            border,
-            format,
-            ty,
-            opt_data
-        ))
+        general! {
+            let returned = self.tex_image_2d(
+                target, level, internal_format, width, height, border, format, ty, opt_data
+            );
+            lock call_stream;
+            {
+                let opt_data = opt_data.map(|data| {
+                    client_data_to_call(&self.inner_gl, call_stream, data,
+                                        width, height, None, format, ty)
+                });
+                check!(call_stream.write_call(Call::tex_image_2d {
+                    target, level, internal_format, width, height, border,
+                    format, ty, opt_data
+                }));
+            }
+        }
     }
 
     fn compressed_tex_image_2d(
@@ -491,7 +811,20 @@ where
         border: GLint,
         data: &[u8],
     ) {
-        unimplemented!("compressed_tex_image_2d");
+        general! {
+            let returned = self.compressed_tex_image_2d(
+                target, level, internal_format, width, height, border, data
+            );
+            lock call_stream;
+            {
+                let image_size = data.len();
+                let data = compressed_data_to_call(&self.inner_gl, call_stream, data);
+                check!(call_stream.write_call(Call::compressed_tex_image_2d {
+                    target, level, internal_format, width, height, border,
+                    data, image_size
+                }));
+            }
+        }
     }
 
     fn compressed_tex_sub_image_2d(
@@ -505,7 +838,20 @@ where
         format: GLenum,
         data: &[u8],
     ) {
-        unimplemented!("compressed_tex_sub_image_2d");
+        general! {
+            let returned = self.compressed_tex_sub_image_2d(
+                target, level, xoffset, yoffset, width, height, format, data
+            );
+            lock call_stream;
+            {
+                let image_size = data.len();
+                let data = compressed_data_to_call(&self.inner_gl, call_stream, data);
+                check!(call_stream.write_call(Call::compressed_tex_sub_image_2d {
+                    target, level, xoffset, yoffset, width, height, format,
+                    data, image_size
+                }));
+            }
+        }
     }
 
     fn tex_image_3d(
@@ -521,18 +867,23 @@ where
         ty: GLenum,
         opt_data: Option<&[u8]>,
     ) {
-        simple!(self.tex_image_3d(
-            target,
-            level,
-            internal_format,
-            width,
-            height,
-            depth,
-            border,
-            format,
-            ty,
-            opt_data
-        ))
+        general! {
+            let returned = self.tex_image_3d(
+                target, level, internal_format, width, height, depth, border, format, ty,
+                opt_data
+            );
+            lock call_stream;
+            {
+                let opt_data = opt_data.map(|data| {
+                    client_data_to_call(&self.inner_gl, call_stream, data,
+                                        width, height, Some(depth), format, ty)
+                });
+                check!(call_stream.write_call(Call::tex_image_3d {
+                    target, level, internal_format, width, height, depth, border,
+                    format, ty, opt_data
+                }));
+            }
+        }
     }
 
     fn copy_tex_image_2d(
@@ -546,7 +897,7 @@ where
         height: GLsizei,
         border: GLint,
     ) {
-        unimplemented!("copy_tex_image_2d");
+        simple!(self.copy_tex_image_2d(target, level, internal_format, x, y, width, height, border))
     }
 
     fn copy_tex_sub_image_2d(
@@ -560,7 +911,7 @@ where
         width: GLsizei,
         height: GLsizei,
     ) {
-        unimplemented!("copy_tex_sub_image_2d");
+        simple!(self.copy_tex_sub_image_2d(target, level, xoffset, yoffset, x, y, width, height))
     }
 
     fn copy_tex_sub_image_3d(
@@ -575,7 +926,9 @@ where
         width: GLsizei,
         height: GLsizei,
     ) {
-        unimplemented!("copy_tex_sub_image_3d");
+        simple!(self.copy_tex_sub_image_3d(
+            target, level, xoffset, yoffset, zoffset, x, y, width, height
+        ))
     }
 
     fn tex_sub_image_2d(
@@ -590,7 +943,20 @@ where
         ty: GLenum,
         data: &[u8],
     ) {
-        unimplemented!("tex_sub_image_2d");
+        general! {
+            let returned = self.tex_sub_image_2d(
+                target, level, xoffset, yoffset, width, height, format, ty, data
+            );
+            lock call_stream;
+            {
+                let data = client_data_to_call(&self.inner_gl, call_stream, data,
+                                               width, height, None, format, ty);
+                check!(call_stream.write_call(Call::tex_sub_image_2d {
+                    target, level, xoffset, yoffset, width, height,
+                    format, ty, data
+                }));
+            }
+        }
     }
 
     fn tex_sub_image_2d_pbo(
@@ -612,7 +978,7 @@ where
             lock call_stream;
             {
                 let offset = tex_image_data_to_call(&self.inner_gl, call_stream,
-                                                    width, height, 1, format, ty,
+                                                    width, height, None, format, ty,
                                                     offset);
                 check!(call_stream.write_call(Call::tex_sub_image_2d_pbo {
                     target, level, xoffset, yoffset, width, height,
@@ -636,9 +1002,21 @@ where
         ty: GLenum,
         data: &[u8],
     ) {
-        simple!(self.tex_sub_image_3d(
-            target, level, xoffset, yoffset, zoffset, width, height, depth, format, ty, data
-        ))
+        general! {
+            let returned = self.tex_sub_image_3d(
+                target, level, xoffset, yoffset, zoffset, width, height, depth, format, ty, data
+            );
+            lock call_stream;
+            {
+                let data = client_data_to_call(&self.inner_gl, call_stream, data,
+                                               width, height, Some(depth), format, ty);
+                check!(call_stream.write_call(Call::tex_sub_image_3d {
+                    target, level, xoffset, yoffset, zoffset,
+                    width, height, depth,
+                    format, ty, data
+                }));
+            }
+        }
     }
 
     fn tex_sub_image_3d_pbo(
@@ -662,7 +1040,7 @@ where
             lock call_stream;
             {
                 let offset = tex_image_data_to_call(&self.inner_gl, call_stream,
-                                                    width, height, depth, format, ty,
+                                                    width, height, Some(depth), format, ty,
                                                     offset);
                 check!(call_stream.write_call(Call::tex_sub_image_3d_pbo {
                     target, level, xoffset, yoffset, zoffset,
@@ -779,19 +1157,21 @@ where
         attachment: GLenum,
         pname: GLenum,
     ) -> GLint {
-        unimplemented!("get_framebuffer_attachment_parameter_iv");
+        simple_with_return_value!(
+            self.get_framebuffer_attachment_parameter_iv(target, attachment, pname)
+        )
     }
 
     fn get_renderbuffer_parameter_iv(&self, target: GLenum, pname: GLenum) -> GLint {
-        unimplemented!("get_renderbuffer_parameter_iv");
+        simple_with_return_value!(self.get_renderbuffer_parameter_iv(target, pname))
     }
 
     fn get_tex_parameter_iv(&self, target: GLenum, name: GLenum) -> GLint {
-        unimplemented!("get_tex_parameter_iv");
+        simple_with_return_value!(self.get_tex_parameter_iv(target, name))
     }
 
     fn get_tex_parameter_fv(&self, target: GLenum, name: GLenum) -> GLfloat {
-        unimplemented!("get_tex_parameter_fv");
+        simple_with_return_value!(self.get_tex_parameter_fv(target, name))
     }
 
     fn tex_parameter_i(&self, target: GLenum, pname: GLenum, param: GLint) {
@@ -843,7 +1223,7 @@ where
     }
 
     fn vertex_attrib_4f(&self, index: GLuint, x: GLfloat, y: GLfloat, z: GLfloat, w: GLfloat) {
-        unimplemented!("vertex_attrib_4f");
+        simple!(self.vertex_attrib_4f(index, x, y, z, w))
     }
 
     fn vertex_attrib_pointer_f32(
@@ -854,7 +1234,7 @@ where
         stride: GLsizei,
         offset: GLuint,
     ) {
-        unimplemented!("vertex_attrib_pointer_f32");
+        simple!(self.vertex_attrib_pointer_f32(index, size, normalized, stride, offset))
     }
 
     fn vertex_attrib_pointer(
@@ -901,11 +1281,24 @@ where
     }
 
     fn validate_program(&self, program: GLuint) {
-        unimplemented!("validate_program");
+        general! {
+            let returned = self.validate_program(program);
+            lock call_stream;
+            {
+                // Record the outcome, so that replay can tell if the program
+                // validates differently there.
+                let mut status = 0;
+                unsafe {
+                    self.inner_gl.get_program_iv(program, VALIDATE_STATUS,
+                                                 std::slice::from_mut(&mut status));
+                }
+                check!(call_stream.write_call(Call::validate_program { program, status }));
+            }
+        }
     }
 
     fn draw_arrays(&self, mode: GLenum, first: GLint, count: GLsizei) {
-        unimplemented!("draw_arrays");
+        simple!(self.draw_arrays(mode, first, count))
     }
 
     fn draw_arrays_instanced(
@@ -915,7 +1308,7 @@ where
         count: GLsizei,
         primcount: GLsizei,
     ) {
-        unimplemented!("draw_arrays_instanced");
+        simple!(self.draw_arrays_instanced(mode, first, count, primcount))
     }
 
     fn draw_elements(
@@ -925,7 +1318,7 @@ where
         element_type: GLenum,
         indices_offset: GLuint,
     ) {
-        unimplemented!("draw_elements");
+        simple!(self.draw_elements(mode, count, element_type, indices_offset))
     }
 
     fn draw_elements_instanced(
@@ -1122,11 +1515,11 @@ where
     }
 
     fn get_active_attrib(&self, program: GLuint, index: GLuint) -> (i32, u32, String) {
-        unimplemented!("get_active_attrib");
+        simple_with_return_value!(self.get_active_attrib(program, index))
     }
 
     fn get_active_uniform(&self, program: GLuint, index: GLuint) -> (i32, u32, String) {
-        unimplemented!("get_active_uniform");
+        simple_with_return_value!(self.get_active_uniform(program, index))
     }
 
     fn get_active_uniforms_iv(
@@ -1135,11 +1528,25 @@ where
         indices: Vec<GLuint>,
         pname: GLenum,
     ) -> Vec<GLint> {
-        unimplemented!("get_active_uniforms_iv");
+        // The inner `Gl` consumes `indices`, so save a copy to record.
+        let recorded_indices = indices.clone();
+        general! {
+            let returned = self.get_active_uniforms_iv(program, indices, pname);
+            lock call_stream;
+            {
+                let call = Call::get_active_uniforms_iv {
+                    program,
+                    indices: check!(recorded_indices.to_call(call_stream)),
+                    pname,
+                    returned: check!(returned.to_call(call_stream)),
+                };
+                check!(call_stream.write_call(call));
+            }
+        }
     }
 
     fn get_active_uniform_block_i(&self, program: GLuint, index: GLuint, pname: GLenum) -> GLint {
-        unimplemented!("get_active_uniform_block_i");
+        simple_with_return_value!(self.get_active_uniform_block_i(program, index, pname))
     }
 
     fn get_active_uniform_block_iv(
@@ -1148,19 +1555,19 @@ where
         index: GLuint,
         pname: GLenum,
     ) -> Vec<GLint> {
-        unimplemented!("get_active_uniform_block_iv");
+        simple_with_return_value!(self.get_active_uniform_block_iv(program, index, pname))
     }
 
     fn get_active_uniform_block_name(&self, program: GLuint, index: GLuint) -> String {
-        unimplemented!("get_active_uniform_block_name");
+        simple_with_return_value!(self.get_active_uniform_block_name(program, index))
     }
 
     fn get_attrib_location(&self, program: GLuint, name: &str) -> c_int {
-        simple!(self.get_attrib_location(program, name))
+        simple_with_return_value!(self.get_attrib_location(program, name))
     }
 
     fn get_frag_data_location(&self, program: GLuint, name: &str) -> c_int {
-        simple!(self.get_frag_data_location(program, name))
+        simple_with_return_value!(self.get_frag_data_location(program, name))
     }
 
     fn get_uniform_location(&self, program: GLuint, name: &str) -> c_int {
@@ -1168,38 +1575,38 @@ where
     }
 
     fn get_program_info_log(&self, program: GLuint) -> String {
-        unimplemented!("get_program_info_log");
+        simple_with_return_value!(self.get_program_info_log(program))
     }
     unsafe fn get_program_iv(&self, program: GLuint, pname: GLenum, result: &mut [GLint]) {
         simple!(self.get_program_iv(program, pname, result))
     }
 
     fn get_program_binary(&self, program: GLuint) -> (Vec<u8>, GLenum) {
-        unimplemented!("get_program_binary");
+        simple_with_return_value!(self.get_program_binary(program))
     }
 
     fn program_binary(&self, program: GLuint, format: GLenum, binary: &[u8]) {
-        unimplemented!("program_binary");
+        simple!(self.program_binary(program, format, binary))
     }
 
     fn program_parameter_i(&self, program: GLuint, pname: GLenum, value: GLint) {
-        unimplemented!("program_parameter_i");
+        simple!(self.program_parameter_i(program, pname, value))
     }
 
     unsafe fn get_vertex_attrib_iv(&self, index: GLuint, pname: GLenum, result: &mut [GLint]) {
-        unimplemented!("get_vertex_attrib_iv");
+        simple!(self.get_vertex_attrib_iv(index, pname, result))
     }
 
     unsafe fn get_vertex_attrib_fv(&self, index: GLuint, pname: GLenum, result: &mut [GLfloat]) {
-        unimplemented!("get_vertex_attrib_fv");
+        simple!(self.get_vertex_attrib_fv(index, pname, result))
     }
 
     fn get_vertex_attrib_pointer_v(&self, index: GLuint, pname: GLenum) -> GLsizeiptr {
-        unimplemented!("get_vertex_attrib_pointer_v");
+        simple_with_return_value!(self.get_vertex_attrib_pointer_v(index, pname))
     }
 
     fn get_buffer_parameter_iv(&self, target: GLuint, pname: GLenum) -> GLint {
-        unimplemented!("get_buffer_parameter_iv");
+        simple_with_return_value!(self.get_buffer_parameter_iv(target, pname))
     }
 
     fn get_shader_info_log(&self, shader: GLuint) -> String {
@@ -1223,7 +1630,7 @@ where
         shader_type: GLuint,
         precision_type: GLuint,
     ) -> (GLint, GLint, GLint) {
-        unimplemented!("get_shader_precision_format");
+        simple_with_return_value!(self.get_shader_precision_format(shader_type, precision_type))
     }
 
     fn compile_shader(&self, shader: GLuint) {
@@ -1283,35 +1690,87 @@ where
     }
 
     fn stencil_mask(&self, mask: GLuint) {
-        unimplemented!("stencil_mask");
+        simple!(self.stencil_mask(mask))
     }
 
     fn stencil_mask_separate(&self, face: GLenum, mask: GLuint) {
-        unimplemented!("stencil_mask_separate");
+        simple!(self.stencil_mask_separate(face, mask))
     }
 
     fn stencil_func(&self, func: GLenum, ref_: GLint, mask: GLuint) {
-        unimplemented!("stencil_func");
+        simple!(self.stencil_func(func, ref_, mask))
     }
 
     fn stencil_func_separate(&self, face: GLenum, func: GLenum, ref_: GLint, mask: GLuint) {
-        unimplemented!("stencil_func_separate");
+        simple!(self.stencil_func_separate(face, func, ref_, mask))
     }
 
     fn stencil_op(&self, sfail: GLenum, dpfail: GLenum, dppass: GLenum) {
-        unimplemented!("stencil_op");
+        simple!(self.stencil_op(sfail, dpfail, dppass))
     }
 
     fn stencil_op_separate(&self, face: GLenum, sfail: GLenum, dpfail: GLenum, dppass: GLenum) {
-        unimplemented!("stencil_op_separate");
+        simple!(self.stencil_op_separate(face, sfail, dpfail, dppass))
     }
 
     fn egl_image_target_texture2d_oes(&self, target: GLenum, image: GLeglImageOES) {
-        unimplemented!("egl_image_target_texture2d_oes");
+        general! {
+            let returned = self.egl_image_target_texture2d_oes(target, image);
+            lock call_stream;
+            {
+                // The image means nothing outside this process, so record the
+                // texture's contents instead.
+                let inner_gl = &self.inner_gl;
+                let (width, height) = match self.egl_image_size {
+                    Some(egl_image_size) => egl_image_size(inner_gl, image),
+                    None => egl_image_size(inner_gl, image),
+                };
+                // External textures can't be attached to a framebuffer, so
+                // read the image through a temporary `TEXTURE_2D` instead.
+                let saved_texture = get_integer(inner_gl, TEXTURE_BINDING_2D) as GLuint;
+                let texture = inner_gl.gen_textures(1)[0];
+                inner_gl.bind_texture(TEXTURE_2D, texture);
+                inner_gl.egl_image_target_texture2d_oes(TEXTURE_2D, image);
+                inner_gl.bind_texture(TEXTURE_2D, saved_texture);
+                let pixels = read_back_image(inner_gl, width, height, |gl| {
+                    gl.framebuffer_texture_2d(READ_FRAMEBUFFER, COLOR_ATTACHMENT0,
+                                              TEXTURE_2D, texture, 0);
+                });
+                inner_gl.delete_textures(&[texture]);
+                // If we couldn't read the image back, record the call anyway,
+                // so the replay can report what it's missing.
+                let pixels = match pixels {
+                    Some(pixels) => Some(check!(pixels.to_call(call_stream))),
+                    None => None,
+                };
+                let call = Call::egl_image_target_texture2d_oes { target, pixels };
+                check!(call_stream.write_call(call));
+            }
+        }
     }
 
     fn egl_image_target_renderbuffer_storage_oes(&self, target: GLenum, image: GLeglImageOES) {
-        unimplemented!("egl_image_target_renderbuffer_storage_oes");
+        general! {
+            let returned = self.egl_image_target_renderbuffer_storage_oes(target, image);
+            lock call_stream;
+            {
+                // As above, record the renderbuffer's contents.
+                let inner_gl = &self.inner_gl;
+                let width = inner_gl.get_renderbuffer_parameter_iv(target, RENDERBUFFER_WIDTH);
+                let height = inner_gl.get_renderbuffer_parameter_iv(target, RENDERBUFFER_HEIGHT);
+                let renderbuffer = get_integer(inner_gl, RENDERBUFFER_BINDING) as GLuint;
+                let pixels = read_back_image(inner_gl, width, height, |gl| {
+                    gl.framebuffer_renderbuffer(READ_FRAMEBUFFER, COLOR_ATTACHMENT0,
+                                                RENDERBUFFER, renderbuffer);
+                });
+                let pixels = match pixels {
+                    Some(pixels) => Some(check!(pixels.to_call(call_stream))),
+                    None => None,
+                };
+                let call = Call::egl_image_target_renderbuffer_storage_oes { target, pixels };
+                check!(call_stream.write_call(call));
+            }
+        }
     }
 
     fn generate_mipmap(&self, target: GLenum) {
@@ -1319,15 +1778,15 @@ where
     }
 
     fn insert_event_marker_ext(&self, message: &str) {
-        unimplemented!("insert_event_marker_ext");
+        simple!(self.insert_event_marker_ext(message))
     }
 
     fn push_group_marker_ext(&self, message: &str) {
-        unimplemented!("push_group_marker_ext");
+        simple!(self.push_group_marker_ext(message))
     }
 
     fn pop_group_marker_ext(&self) {
-        unimplemented!("pop_group_marker_ext");
+        simple!(self.pop_group_marker_ext())
     }
 
     fn debug_message_insert_khr(
@@ -1338,63 +1797,100 @@ where
         severity: GLenum,
         message: &str,
     ) {
-        unimplemented!("debug_message_insert_khr");
+        simple!(self.debug_message_insert_khr(source, type_, id, severity, message))
     }
 
     fn push_debug_group_khr(&self, source: GLenum, id: GLuint, message: &str) {
-        unimplemented!("push_debug_group_khr");
+        simple!(self.push_debug_group_khr(source, id, message))
     }
 
     fn pop_debug_group_khr(&self) {
-        unimplemented!("pop_debug_group_khr");
+        simple!(self.pop_debug_group_khr())
     }
 
     fn fence_sync(&self, condition: GLenum, flags: GLbitfield) -> GLsync {
-        unimplemented!("fence_sync");
+        general! {
+            let returned = self.fence_sync(condition, flags);
+            lock call_stream;
+            {
+                let id = self.new_sync_id(returned);
+                check!(call_stream.write_call(Call::fence_sync { condition, flags, returned: id }));
+            }
+        }
     }
 
     fn client_wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: GLuint64) {
-        unimplemented!("client_wait_sync");
+        general! {
+            let returned = self.client_wait_sync(sync, flags, timeout);
+            lock call_stream;
+            {
+                let sync = self.sync_id(sync, call_stream);
+                check!(call_stream.write_call(Call::client_wait_sync { sync, flags, timeout }));
+            }
+        }
     }
 
     fn wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: GLuint64) {
-        unimplemented!("wait_sync");
+        general! {
+            let returned = self.wait_sync(sync, flags, timeout);
+            lock call_stream;
+            {
+                let sync = self.sync_id(sync, call_stream);
+                check!(call_stream.write_call(Call::wait_sync { sync, flags, timeout }));
+            }
+        }
     }
 
     fn delete_sync(&self, sync: GLsync) {
-        unimplemented!("delete_sync");
+        general! {
+            let returned = self.delete_sync(sync);
+            lock call_stream;
+            {
+                let sync = self.forget_sync_id(sync, call_stream);
+                check!(call_stream.write_call(Call::delete_sync { sync }));
+            }
+        }
     }
 
     fn texture_range_apple(&self, target: GLenum, data: &[u8]) {
-        unimplemented!("texture_range_apple");
+        general! {
+            let returned = self.texture_range_apple(target, data);
+            lock call_stream;
+            {
+                // The range only describes where the application keeps client
+                // storage for textures, so its contents don't matter.
+                let length = data.len();
+                check!(call_stream.write_call(Call::texture_range_apple { target, length }));
+            }
+        }
     }
 
     fn gen_fences_apple(&self, n: GLsizei) -> Vec<GLuint> {
-        unimplemented!("gen_fences_apple");
+        simple_with_return_value!(self.gen_fences_apple(n))
     }
 
     fn delete_fences_apple(&self, fences: &[GLuint]) {
-        unimplemented!("delete_fences_apple");
+        simple!(self.delete_fences_apple(fences))
     }
 
     fn set_fence_apple(&self, fence: GLuint) {
-        unimplemented!("set_fence_apple");
+        simple!(self.set_fence_apple(fence))
     }
 
     fn finish_fence_apple(&self, fence: GLuint) {
-        unimplemented!("finish_fence_apple");
+        simple!(self.finish_fence_apple(fence))
     }
 
     fn test_fence_apple(&self, fence: GLuint) {
-        unimplemented!("test_fence_apple");
+        simple!(self.test_fence_apple(fence))
     }
 
     fn test_object_apple(&self, object: GLenum, name: GLuint) -> GLboolean {
-        unimplemented!("test_object_apple");
+        simple_with_return_value!(self.test_object_apple(object, name))
     }
 
     fn finish_object_apple(&self, object: GLenum, name: GLuint) {
-        unimplemented!("finish_object_apple");
+        simple!(self.finish_object_apple(object, name))
     }
 
     // GL_KHR_blend_equation_advanced
@@ -1410,11 +1906,11 @@ where
         index: GLuint,
         name: &str,
     ) {
-        unimplemented!("bind_frag_data_location_indexed");
+        simple!(self.bind_frag_data_location_indexed(program, color_number, index, name))
     }
 
     fn get_frag_data_index(&self, program: GLuint, name: &str) -> GLint {
-        unimplemented!("get_frag_data_index");
+        simple_with_return_value!(self.get_frag_data_index(program, name))
     }
 
     // GL_KHR_debug
@@ -1441,7 +1937,18 @@ where
         unpack_premultiply_alpha: GLboolean,
         unpack_unmultiply_alpha: GLboolean,
     ) {
-        unimplemented!("copy_texture_chromium");
+        simple!(self.copy_texture_chromium(
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            internal_format,
+            dest_type,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha
+        ))
     }
 
     fn copy_sub_texture_chromium(
@@ -1461,7 +1968,22 @@ where
         unpack_premultiply_alpha: GLboolean,
         unpack_unmultiply_alpha: GLboolean,
     ) {
-        unimplemented!("copy_sub_texture_chromium");
+        simple!(self.copy_sub_texture_chromium(
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            x_offset,
+            y_offset,
+            x,
+            y,
+            width,
+            height,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha
+        ))
     }
 
     // GL_ANGLE_copy_texture_3d
@@ -1478,7 +2000,18 @@ where
         unpack_premultiply_alpha: GLboolean,
         unpack_unmultiply_alpha: GLboolean,
     ) {
-        unimplemented!("copy_texture_3d_angle");
+        simple!(self.copy_texture_3d_angle(
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            internal_format,
+            dest_type,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha
+        ))
     }
 
     fn copy_sub_texture_3d_angle(
@@ -1501,6 +2034,24 @@ where
         unpack_premultiply_alpha: GLboolean,
         unpack_unmultiply_alpha: GLboolean,
     ) {
-        unimplemented!("copy_sub_texture_3d_angle");
+        simple!(self.copy_sub_texture_3d_angle(
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            x_offset,
+            y_offset,
+            z_offset,
+            x,
+            y,
+            z,
+            width,
+            height,
+            depth,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha
+        ))
     }
 }
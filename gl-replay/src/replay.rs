@@ -2,16 +2,26 @@ use gleam::gl::Gl;
 
 #[allow(unused_imports)]
 use gleam::gl::{
-    GLbitfield, GLclampf, GLenum, GLfloat, GLint, GLsizei, GLsizeiptr, GLuint, GLvoid,
+    GLbitfield, GLclampf, GLenum, GLfloat, GLint, GLsizei, GLsizeiptr, GLsync, GLuint, GLvoid,
 };
 
-use crate::call::{Call, TexImageData};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::call::{Call, SyncId, TexImageData};
 use crate::form::{Seq, Str, Var};
 use crate::pixels::{Pixels, PixelsForm};
 use crate::raw;
 use crate::var::DeserializeAs;
 use crate::FileRecording;
 
+mod copy_texture;
+mod names;
+mod relink;
+mod snapshot;
+
+pub use names::Namespace;
+
 /// A `Gl` method argument type.
 ///
 /// Some types of arguments are stored directly in the `Call` variant, like
@@ -78,6 +88,12 @@ impl<'v> Parameter<'v, Var<Str>> for &'v str {
     }
 }
 
+impl<'v> Parameter<'v, Var<Str>> for String {
+    fn from_call(in_call: Var<Str>, variable: &'v [u8]) -> String {
+        <&str>::from_call(in_call, variable).to_owned()
+    }
+}
+
 impl<'v> Parameter<'v, Var<PixelsForm>> for Pixels<'static> {
     fn from_call(in_call: Var<PixelsForm>, variable: &'v [u8]) -> Pixels<'static> {
         let mut variable = &variable[in_call.offset()..];
@@ -94,6 +110,28 @@ where
     }
 }
 
+macro_rules! tuple_parameter_types {
+    ( $( ( $( $type:ident $form:ident $value:ident ),* ) )* ) => {
+        $(
+            impl<'v, $( $type, $form ),* > Parameter<'v, ( $( $form, )* )> for ( $( $type, )* )
+            where
+                $( $type: Parameter<'v, $form>, )*
+            {
+                fn from_call(in_call: ( $( $form, )* ), variable: &'v [u8]) -> Self {
+                    let ( $( $value, )* ) = in_call;
+                    ( $( $type::from_call($value, variable), )* )
+                }
+            }
+        )*
+    }
+}
+
+// Tuples are stored as tuples of their elements' forms.
+tuple_parameter_types! {
+    (A FA a, B FB b)
+    (A FA a, B FB b, C FC c)
+}
+
 pub fn get_parameter<'v, P, C>(in_call: C, variable: &'v [u8]) -> P
 where
     P: Parameter<'v, C>,
@@ -111,6 +149,40 @@ fn call_to_tex_image_data_offset<'v>(in_call: TexImageData, variable: &'v [u8])
     }
 }
 
+/// Call `f` to replay a texture upload.
+///
+/// If `from_client` is true, the upload's data was recorded from client
+/// memory, and the recorder kept only the bytes GL would read, so we must
+/// clear the unpacking skip state while `f` runs. The row length, image
+/// height, and alignment still apply as the recording established them.
+fn replay_upload<F: FnOnce()>(gl: &dyn Gl, from_client: bool, f: F) {
+    if from_client {
+        snapshot::with_unpack_skips_cleared(gl, f);
+    } else {
+        f();
+    }
+}
+
+/// Return the slice to pass as the `data` argument of a compressed texture
+/// upload method.
+///
+/// If `in_call` refers to data saved in the variable section, return that.
+/// Otherwise, it is an offset into the PIXEL_UNPACK_BUFFER; `gleam` still
+/// wants a slice, so we must fabricate one whose address is the offset, just
+/// as the recorded application did.
+fn call_to_compressed_data<'v>(
+    in_call: TexImageData,
+    image_size: usize,
+    variable: &'v [u8],
+) -> &'v [u8] {
+    match in_call {
+        TexImageData::Buf(var) => get_slice(var, variable),
+        TexImageData::Offset(offset) => unsafe {
+            std::slice::from_raw_parts(offset as *const u8, image_size)
+        },
+    }
+}
+
 macro_rules! simple {
     ( $locals:ident : $method:ident ( $( $arg:ident ),* $(,)? ) ) =>
     {
@@ -125,6 +197,15 @@ macro_rules! simple {
 }
 
 macro_rules! check_return_value {
+    // If the return value is stored in the variable-length data, the caller
+    // must specify the type to deserialize it as.
+    ( $locals:ident : $method:ident ( $( $arg:ident ),* ): $returned:ident as $type:ty ) => {
+        {
+            let $returned: $type = get_parameter( $returned, & $locals .variable );
+            check_return_value!( $locals : $method ( $( $arg ),* ): $returned )
+        }
+    };
+
     ( $locals:ident : $method:ident ( $( $arg:ident ),* ): $returned:ident ) => {
         {
             let actual = $locals .gl. $method (
@@ -134,11 +215,9 @@ macro_rules! check_return_value {
             );
             let expected = $returned;
             if expected != actual {
-                eprintln!("gl-replay: method {} (serial {}) returned unexpected value",
-                          stringify!( $method ), $locals .serial);
-                eprintln!("expected: {:?}", expected);
-                eprintln!("actual: {:?}", actual);
-                panic!("replay cannot proceed");
+                $locals .diverged(stringify!( $method ),
+                                  format!("{:?}", expected),
+                                  format!("{:?}", actual))?;
             }
         }
     }
@@ -150,18 +229,30 @@ macro_rules! check_returned_vector {
             let actual = $locals .gl. $method ( $( $arg ),* );
             let expected = get_slice( $returned, & $locals .variable );
             if expected != &actual[..] {
-                eprintln!("gl-replay: method {} (serial {}) returned unexpected value",
-                          stringify!( $method ), $locals .serial);
-                if expected.len() + actual.len() < 1000 {
-                    eprintln!("expected: {:?}", expected);
-                    eprintln!("actual: {:?}", actual);
-                }
-                panic!("replay cannot proceed");
+                $locals .diverged(stringify!( $method ),
+                                  describe_slice(expected),
+                                  describe_slice(&actual))?;
             }
         }
     }
 }
 
+/// Call a `get_query_object_*` method, and check its result according to the
+/// query's `QueryCheck`.
+macro_rules! check_query_result {
+    ( $locals:ident : $method:ident ( $id:ident, $pname:ident ): $returned:ident ) => {{
+        let actual = $locals.gl.$method($id, $pname);
+        let check = $locals.replayer.query_check($id, $pname);
+        if !check.accepts($returned as i128, actual as i128) {
+            $locals.diverged(
+                stringify!($method),
+                format!("{:?} (checked as {:?})", $returned, check),
+                format!("{:?}", actual),
+            )?;
+        }
+    }};
+}
+
 macro_rules! check_filled_slice {
     ( $locals:ident : $method:ident ( $( $arg:ident ),* ): $result:ident ) => {
         check_filled_slice!(@combined $locals : $method ( $( $arg ),* ):
@@ -187,14 +278,351 @@ macro_rules! check_filled_slice {
             let mut $result = expected.to_owned();
             $call;
             if expected != & $result [..] {
-                eprintln!("gl-replay: method {} (serial {}) returned unexpected value",
-                          stringify!( $method ), $locals .serial);
-                if expected.len() + $result .len() < 1000 {
-                    eprintln!("expected: {:?}", expected);
-                    eprintln!("actual: {:?}", $result );
-                }
-                panic!("replay cannot proceed");
+                $locals .diverged(stringify!( $method ),
+                                  describe_slice(expected),
+                                  describe_slice(& $result))?;
+            }
+        }
+    }
+}
+
+/// A divergence between the replay and the recording.
+///
+/// This describes a call whose results on the replay `Gl` differed from those
+/// the recorded application saw.
+#[derive(Clone, Debug)]
+pub struct ReplayError {
+    /// The serial number of the call in the recording.
+    pub serial: usize,
+
+    /// The name of the method that diverged.
+    pub method: &'static str,
+
+    /// A description of the recorded result.
+    pub expected: String,
+
+    /// A description of the result during replay.
+    pub actual: String,
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "method {} (serial {}) returned unexpected value\nexpected: {}\nactual: {}",
+            self.method, self.serial, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Describe `values` for a `ReplayError`, eliding them if there are many.
+fn describe_slice<T: std::fmt::Debug>(values: &[T]) -> String {
+    if values.len() < 500 {
+        format!("{:?}", values)
+    } else {
+        format!("{} values", values.len())
+    }
+}
+
+/// Save `expected` and `actual` as images named after `serial`, and return
+/// descriptions of them for a `ReplayError`.
+///
+/// If `Pixels::write_image` can't save pixels in their format, just describe
+/// their bytes.
+fn save_comparison_images(serial: usize, expected: &Pixels, actual: &Pixels) -> (String, String) {
+    if !expected.can_write_image() {
+        return (
+            describe_slice(expected.bytes.as_ref()),
+            describe_slice(actual.bytes.as_ref()),
+        );
+    }
+    let describe = |pixels: &Pixels, kind: &str| {
+        let filename = format!("{}-{}.png", kind, serial);
+        pixels.write_image(&filename);
+        format!(
+            "{}x{} image, saved to '{}'",
+            pixels.width, pixels.height, filename
+        )
+    };
+    (describe(expected, "expected"), describe(actual, "actual"))
+}
+
+/// How to compare a query object's result with the recorded value.
+#[derive(Copy, Clone, Debug)]
+pub enum QueryCheck {
+    /// The result must match exactly.
+    Exact,
+
+    /// Don't compare the result.
+    Ignore,
+
+    /// The result must be within the given fraction of the recorded value.
+    /// For example, `Tolerance(0.1)` accepts results within 10%.
+    Tolerance(f64),
+}
+
+impl QueryCheck {
+    /// Return the default check for queries targeting `target`.
+    ///
+    /// Timer results depend on the machine and its load, so we can't expect
+    /// them to match. Everything else, like occlusion query sample counts,
+    /// should be reproducible.
+    fn default_for(target: GLenum) -> QueryCheck {
+        match target {
+            gleam::gl::TIME_ELAPSED | gleam::gl::TIMESTAMP => QueryCheck::Ignore,
+            _ => QueryCheck::Exact,
+        }
+    }
+
+    fn accepts(self, expected: i128, actual: i128) -> bool {
+        match self {
+            QueryCheck::Exact => expected == actual,
+            QueryCheck::Ignore => true,
+            QueryCheck::Tolerance(fraction) => {
+                (actual - expected).abs() as f64 <= expected.abs() as f64 * fraction
+            }
+        }
+    }
+}
+
+/// Options and state for replaying `Gl` calls.
+///
+/// Some calls can only be replayed correctly with information gathered from
+/// earlier calls in the recording, so replaying a recording one call at a time
+/// requires passing the same `Replayer` to each `replay_one` call.
+#[derive(Default)]
+pub struct Replayer {
+    /// If this is `Some`, then when the replay `Gl` rejects a program binary,
+    /// we link the program from the sources that produced that binary during
+    /// recording instead.
+    program_sources: Option<relink::SourceTracker>,
+
+    /// Live sync objects, indexed by the ids the recorder assigned them.
+    syncs: HashMap<SyncId, GLsync>,
+
+    /// The extensions the replay `Gl` supports, once we've needed to know.
+    extensions: Option<HashSet<String>>,
+
+    /// The sizes of texture levels, for emulating texture copies.
+    texture_sizes: copy_texture::TextureSizes,
+
+    /// How to check query results, by query target, where the caller has
+    /// overridden `QueryCheck::default_for`.
+    query_checks: HashMap<GLenum, QueryCheck>,
+
+    /// The target each query object was last used with.
+    query_targets: HashMap<GLuint, GLenum>,
+
+    /// The live names of the objects the recording refers to.
+    names: names::Names,
+
+    /// If true, collect divergences in `divergences` and carry on, rather
+    /// than stopping at the first one.
+    continue_on_mismatch: bool,
+
+    /// The divergences from the recording seen so far, if
+    /// `continue_on_mismatch` is set.
+    divergences: Vec<ReplayError>,
+
+    /// True if fast-forwarding has skipped a draw or clear.
+    skipped_rendering: bool,
+}
+
+impl Replayer {
+    pub fn new() -> Replayer {
+        Replayer::default()
+    }
+
+    /// Link programs from source when the replay `Gl` rejects their binaries.
+    ///
+    /// Program binaries generally only work with the OpenGL implementation
+    /// that produced them. If `relink` is true, then when `program_binary`
+    /// fails, the replay links the program from the shader sources recorded
+    /// before the application retrieved that binary with `get_program_binary`.
+    /// If the recording doesn't include those sources, replay reports the
+    /// rejected binary as a `ReplayError`.
+    pub fn relink_rejected_program_binaries(mut self, relink: bool) -> Replayer {
+        self.program_sources = if relink {
+            Some(relink::SourceTracker::default())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Check the results of queries targeting `target` as `check` says.
+    ///
+    /// By default, timer queries (`TIME_ELAPSED` and `TIMESTAMP`) are ignored,
+    /// and all others must match exactly. Whether a query's result is
+    /// available yet depends on timing, so that is never checked. Either way,
+    /// the replay `Gl` still performs every query, so a replay can be profiled.
+    pub fn check_queries(mut self, target: GLenum, check: QueryCheck) -> Replayer {
+        self.query_checks.insert(target, check);
+        self
+    }
+
+    /// Carry on replaying after a call's results diverge from the recording.
+    ///
+    /// Normally, `replay` and `replay_one` return a `ReplayError` for the
+    /// first divergence they find. If `carry_on` is true, they collect every
+    /// divergence instead, for retrieval with `divergences`, so that a single
+    /// run can report all of them.
+    pub fn continue_on_mismatch(mut self, carry_on: bool) -> Replayer {
+        self.continue_on_mismatch = carry_on;
+        self
+    }
+
+    /// Return the divergences from the recording collected so far.
+    ///
+    /// This is always empty unless `continue_on_mismatch` is set.
+    pub fn divergences(&self) -> &[ReplayError] {
+        &self.divergences
+    }
+
+    /// Report a divergence from the recording.
+    ///
+    /// If `continue_on_mismatch` is set, add `error` to `divergences` and
+    /// return `Ok`. Otherwise, return `error`. Embedders that check their own
+    /// recorded events can use this to treat their divergences the same way.
+    pub fn report_divergence(&mut self, error: ReplayError) -> Result<(), ReplayError> {
+        if self.continue_on_mismatch {
+            self.divergences.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    pub fn replay(
+        &mut self,
+        gl: &dyn Gl,
+        recording: &FileRecording<Call>,
+    ) -> Result<(), ReplayError> {
+        self.replay_range(gl, recording, 0..recording.calls.len())
+    }
+
+    /// Replay the calls in `recording` whose serial numbers fall in `range`.
+    ///
+    /// The calls before `range` are fast-forwarded, as by `fast_forward_one`:
+    /// only those needed to establish the state that the calls in `range`
+    /// depend on are replayed. Calls after `range` are not replayed at all.
+    ///
+    /// A `range` that starts after it ends is treated as empty: the calls
+    /// before its end are fast-forwarded, and none are replayed in full.
+    pub fn replay_range(
+        &mut self,
+        gl: &dyn Gl,
+        recording: &FileRecording<Call>,
+        range: Range<usize>,
+    ) -> Result<(), ReplayError> {
+        let start = range.start.min(range.end);
+        let mut locals = Locals {
+            gl,
+            variable: &recording.variable,
+            serial: 0,
+            fast_forward: false,
+            replayer: self,
+        };
+        for (serial, call) in recording.calls.iter().enumerate().take(range.end) {
+            locals.serial = serial;
+            locals.fast_forward = serial < start;
+            replay_one_with_locals(&mut locals, call)?;
+        }
+        Ok(())
+    }
+
+    pub fn replay_one(
+        &mut self,
+        gl: &dyn Gl,
+        call: &Call,
+        variable: &[u8],
+        serial: usize,
+    ) -> Result<(), ReplayError> {
+        let mut locals = Locals {
+            gl,
+            variable,
+            serial,
+            fast_forward: false,
+            replayer: self,
+        };
+        replay_one_with_locals(&mut locals, call)
+    }
+
+    /// Replay `call` only if later calls might depend on its effects.
+    ///
+    /// This skips draws, clears, and calls that only read back results into
+    /// client memory, but still replays everything that creates objects,
+    /// uploads or copies data, or sets state. Fast-forwarding through the start
+    /// of a long recording reaches the part of interest much sooner than
+    /// replaying it in full.
+    ///
+    /// Once a draw or clear has been skipped, framebuffer and texture contents
+    /// may not match the recording, so the replay stops comparing pixels read
+    /// back from them. See `skipped_rendering`.
+    pub fn fast_forward_one(
+        &mut self,
+        gl: &dyn Gl,
+        call: &Call,
+        variable: &[u8],
+        serial: usize,
+    ) -> Result<(), ReplayError> {
+        let mut locals = Locals {
+            gl,
+            variable,
+            serial,
+            fast_forward: true,
+            replayer: self,
+        };
+        replay_one_with_locals(&mut locals, call)
+    }
+
+    /// Return true if fast-forwarding has skipped a draw or clear.
+    ///
+    /// If so, framebuffer and texture contents may not match the recording, so
+    /// the replay doesn't compare pixels read back from them. Embedders that
+    /// check contents with their own recorded events should do likewise.
+    pub fn skipped_rendering(&self) -> bool {
+        self.skipped_rendering
+    }
+
+    /// Return the replay `Gl`'s name for the object the recording calls
+    /// `recorded`.
+    ///
+    /// The replay translates the object names in `Call`s itself, but embedders
+    /// whose own recorded events refer to `Gl` objects need this to do the
+    /// same.
+    pub fn live_name(&self, namespace: Namespace, recorded: GLuint) -> GLuint {
+        self.names.get(namespace, recorded)
+    }
+
+    /// Return true if the replay `Gl` supports the extension named `name`.
+    fn has_extension(&mut self, gl: &dyn Gl, name: &str) -> bool {
+        let extensions = self.extensions.get_or_insert_with(|| {
+            let mut count = [0];
+            unsafe {
+                gl.get_integer_v(gleam::gl::NUM_EXTENSIONS, &mut count);
             }
+            (0..count[0] as GLuint)
+                .map(|i| gl.get_string_i(gleam::gl::EXTENSIONS, i))
+                .collect()
+        });
+        extensions.contains(name)
+    }
+
+    /// Return how we should check a `get_query_object_*` result for query `id`
+    /// and parameter `pname`.
+    fn query_check(&self, id: GLuint, pname: GLenum) -> QueryCheck {
+        if pname == gleam::gl::QUERY_RESULT_AVAILABLE {
+            return QueryCheck::Ignore;
+        }
+        match self.query_targets.get(&id) {
+            Some(target) => match self.query_checks.get(target) {
+                Some(&check) => check,
+                None => QueryCheck::default_for(*target),
+            },
+            None => QueryCheck::Exact,
         }
     }
 }
@@ -203,33 +631,234 @@ struct Locals<'g> {
     gl: &'g dyn Gl,
     variable: &'g [u8],
     serial: usize,
+
+    /// If true, skip calls that only draw or read back results.
+    fast_forward: bool,
+
+    replayer: &'g mut Replayer,
 }
 
-pub fn replay(gl: &dyn Gl, recording: &FileRecording<Call>) {
-    let mut locals = Locals {
-        gl,
-        variable: &recording.variable,
-        serial: 0,
-    };
-    for (serial, call) in recording.calls.iter().enumerate() {
-        locals.serial = serial;
-        replay_one_with_locals(&locals, call);
+impl Locals<'_> {
+    fn has_extension(&mut self, name: &str) -> bool {
+        self.replayer.has_extension(self.gl, name)
+    }
+
+    /// Call `generate` to create objects, and note that the objects the
+    /// recording calls `recorded` got the names it returned.
+    fn generate<F>(&mut self, namespace: Namespace, recorded: Var<Seq<GLuint>>, generate: F)
+    where
+        F: FnOnce(&dyn Gl) -> Vec<GLuint>,
+    {
+        let recorded = get_slice(recorded, self.variable);
+        let live = generate(self.gl);
+        self.replayer.names.insert(namespace, recorded, &live);
+    }
+
+    /// Report that `method` produced `actual` where the recording has
+    /// `expected`.
+    fn diverged(
+        &mut self,
+        method: &'static str,
+        expected: String,
+        actual: String,
+    ) -> Result<(), ReplayError> {
+        let serial = self.serial;
+        self.replayer.report_divergence(ReplayError {
+            serial,
+            method,
+            expected,
+            actual,
+        })
+    }
+
+    /// Perform `copy` without the extension `method` belongs to, and report
+    /// it if the emulation doesn't support it.
+    ///
+    /// If `allocate` is `Some((internal_format, dest_type))`, first allocate
+    /// the destination level to match the source, as the non-`sub` methods do.
+    fn emulate_copy(
+        &mut self,
+        method: &'static str,
+        copy: copy_texture::TextureCopy,
+        allocate: Option<(GLint, GLenum)>,
+    ) -> Result<(), ReplayError> {
+        let gl = self.gl;
+        let result = self
+            .replayer
+            .texture_sizes
+            .copy_source(copy.source_id, copy.source_level)
+            .and_then(|source| {
+                if let Some((internal_format, dest_type)) = allocate {
+                    copy_texture::allocate(
+                        gl,
+                        copy.dest_target,
+                        copy.dest_id,
+                        copy.dest_level,
+                        internal_format,
+                        dest_type,
+                        &source,
+                    )?;
+                }
+                copy.emulate(gl, &source)
+            });
+        match result {
+            Ok(()) => Ok(()),
+            Err(unsupported) => self.diverged(
+                method,
+                "a copy the texture copy emulation supports".to_string(),
+                unsupported.0,
+            ),
+        }
+    }
+
+    /// Report that the recorder couldn't read back the contents of the
+    /// EGLImage `method` used, so we have nothing to replay in its place.
+    fn missing_egl_image(&mut self, method: &'static str) -> Result<(), ReplayError> {
+        self.diverged(
+            method,
+            "the EGLImage's contents".to_string(),
+            "nothing; the recorder couldn't read the image back".to_string(),
+        )
+    }
+
+    /// Return the live sync object the recording calls `id`, as passed to
+    /// `method`.
+    ///
+    /// If the recording never created `id`, report that and return `None`, so
+    /// the caller can skip the call.
+    fn sync(&mut self, method: &'static str, id: SyncId) -> Result<Option<GLsync>, ReplayError> {
+        if id == 0 {
+            return Ok(Some(std::ptr::null()));
+        }
+        match self.replayer.syncs.get(&id) {
+            Some(&sync) => Ok(Some(sync)),
+            None => {
+                self.diverged(
+                    method,
+                    "a sync object created by fence_sync".to_string(),
+                    format!("unknown sync object {}", id),
+                )?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Return the live names of the objects the recording calls `recorded`.
+    fn live_names(&self, namespace: Namespace, recorded: Var<Seq<GLuint>>) -> Vec<GLuint> {
+        let recorded = get_slice(recorded, self.variable);
+        self.replayer.names.get_all(namespace, recorded)
     }
 }
 
-pub fn replay_one(gl: &dyn Gl, call: &Call, variable: &[u8], serial: usize) {
-    let locals = Locals {
-        gl,
-        variable,
-        serial,
-    };
-    replay_one_with_locals(&locals, call);
+/// Return true if `call` draws or clears.
+///
+/// Fast-forwarding skips these, so afterwards the contents of framebuffers and
+/// textures may not match the recording.
+fn renders(call: &Call) -> bool {
+    use Call::*;
+    matches!(
+        call,
+        clear { .. }
+            | draw_arrays { .. }
+            | draw_arrays_instanced { .. }
+            | draw_elements { .. }
+            | draw_elements_instanced { .. }
+    )
+}
+
+/// Return true if `call` only reads back results into client memory.
+///
+/// Skipping such calls leaves intact the objects, contents, and bindings that
+/// later calls depend on. Calls that create objects or map their names, like
+/// `gen_textures` or `get_uniform_location`, and calls that read back into
+/// buffer objects, like `read_pixels_into_pbo`, must always be replayed.
+fn only_reads(call: &Call) -> bool {
+    use Call::*;
+    matches!(
+        call,
+        read_pixels { .. }
+            | read_pixels_into_buffer { .. }
+            | get_tex_image_into_buffer { .. }
+            | is_enabled { .. }
+            | is_shader { .. }
+            | is_texture { .. }
+            | is_framebuffer { .. }
+            | is_renderbuffer { .. }
+            | check_frame_buffer_status { .. }
+            | get_shader_iv { .. }
+            | get_program_iv { .. }
+            | get_attrib_location { .. }
+            | get_frag_data_location { .. }
+            | get_frag_data_index { .. }
+            | get_query_object_iv { .. }
+            | get_query_object_uiv { .. }
+            | get_query_object_i64v { .. }
+            | get_query_object_ui64v { .. }
+            | get_uniform_block_index { .. }
+            | get_uniform_indices { .. }
+            | get_active_uniform_block_i { .. }
+            | get_active_uniform_block_iv { .. }
+            | get_active_uniform_block_name { .. }
+            | get_active_attrib { .. }
+            | get_active_uniform { .. }
+            | get_active_uniforms_iv { .. }
+            | get_program_info_log { .. }
+            | get_shader_precision_format { .. }
+            | get_program_binary { .. }
+            | get_vertex_attrib_iv { .. }
+            | get_vertex_attrib_fv { .. }
+            | get_vertex_attrib_pointer_v { .. }
+            | get_buffer_parameter_iv { .. }
+            | get_framebuffer_attachment_parameter_iv { .. }
+            | get_renderbuffer_parameter_iv { .. }
+            | get_tex_parameter_iv { .. }
+            | get_tex_parameter_fv { .. }
+            | test_fence_apple { .. }
+            | test_object_apple { .. }
+    )
+}
+
+/// Replay `recording` on `gl`, with the default options.
+pub fn replay(gl: &dyn Gl, recording: &FileRecording<Call>) -> Result<(), ReplayError> {
+    Replayer::new().replay(gl, recording)
+}
+
+/// Replay a single `call` on `gl`, with the default options.
+///
+/// Each call gets a fresh `Replayer`, so calls that depend on information
+/// gathered from earlier calls, like translated object names or sync objects,
+/// may not replay correctly. Use `Replayer::replay_one` to avoid this.
+pub fn replay_one(
+    gl: &dyn Gl,
+    call: &Call,
+    variable: &[u8],
+    serial: usize,
+) -> Result<(), ReplayError> {
+    Replayer::new().replay_one(gl, call, variable, serial)
 }
 
 #[allow(unused_variables)]
-fn replay_one_with_locals(locals: &Locals, call: &Call) {
+fn replay_one_with_locals(locals: &mut Locals, call: &Call) -> Result<(), ReplayError> {
     let gl = locals.gl;
-    let call = *call;
+    let recorded = *call;
+    let mut call = locals.replayer.names.translate(&recorded);
+    snapshot::external_targets_as_2d(&mut call);
+    // The source tracker only uses program and shader names to match up the
+    // calls it observes, so it can work with recorded names, which it sees
+    // even for the objects created by this call.
+    if let Some(program_sources) = &mut locals.replayer.program_sources {
+        program_sources.observe(&recorded, locals.variable);
+    }
+    locals.replayer.texture_sizes.observe(&call);
+    if locals.fast_forward {
+        if renders(&call) {
+            locals.replayer.skipped_rendering = true;
+            return Ok(());
+        }
+        if only_reads(&call) {
+            return Ok(());
+        }
+    }
     use Call::*;
     match call {
         active_texture { texture } => {
@@ -274,20 +903,43 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         enable_vertex_attrib_array { index } => {
             gl.enable_vertex_attrib_array(index);
         }
-        gen_buffers { n, returned } => check_returned_vector!(locals: gen_buffers(n): returned),
+        gen_buffers { n, returned } => {
+            locals.generate(Namespace::Buffer, returned, |gl| gl.gen_buffers(n))
+        }
         gen_framebuffers { n, returned } => {
-            check_returned_vector!(locals: gen_framebuffers(n): returned)
+            locals.generate(Namespace::Framebuffer, returned, |gl| {
+                gl.gen_framebuffers(n)
+            })
+        }
+        gen_queries { n, returned } => {
+            locals.generate(Namespace::Query, returned, |gl| gl.gen_queries(n))
         }
-        gen_queries { n, returned } => check_returned_vector!(locals: gen_queries(n): returned),
         gen_renderbuffers { n, returned } => {
-            check_returned_vector!(locals: gen_renderbuffers(n): returned)
+            locals.generate(Namespace::Renderbuffer, returned, |gl| {
+                gl.gen_renderbuffers(n)
+            })
+        }
+        gen_textures { n, returned } => {
+            locals.generate(Namespace::Texture, returned, |gl| gl.gen_textures(n))
         }
-        gen_textures { n, returned } => check_returned_vector!(locals: gen_textures(n): returned),
         gen_vertex_arrays { n, returned } => {
-            check_returned_vector!(locals: gen_vertex_arrays(n): returned)
+            locals.generate(Namespace::VertexArray, returned, |gl| {
+                gl.gen_vertex_arrays(n)
+            })
+        }
+        // Apple vertex array objects behave just like core vertex array
+        // objects, so use those if the extension isn't available.
+        gen_vertex_arrays_apple { n, returned } => {
+            if locals.has_extension("GL_APPLE_vertex_array_object") {
+                locals.generate(Namespace::VertexArrayApple, returned, |gl| {
+                    gl.gen_vertex_arrays_apple(n)
+                })
+            } else {
+                locals.generate(Namespace::VertexArrayApple, returned, |gl| {
+                    gl.gen_vertex_arrays(n)
+                })
+            }
         }
-
-        gen_vertex_arrays_apple { n, returned } => unimplemented!("gen_vertex_arrays_apple"),
         line_width { width } => {
             gl.line_width(width);
         }
@@ -312,20 +964,22 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             format,
             ty,
             opt_data,
-        } => simple!(
-            locals:
-                tex_image_2d(
-                    target,
-                    level,
-                    internal_format,
-                    width,
-                    height,
-                    border,
-                    format,
-                    ty,
-                    opt_data,
-                )
-        ),
+        } => replay_upload(gl, opt_data.is_some(), || {
+            simple!(
+                locals:
+                    tex_image_2d(
+                        target,
+                        level,
+                        internal_format,
+                        width,
+                        height,
+                        border,
+                        format,
+                        ty,
+                        opt_data,
+                    )
+            )
+        }),
         tex_image_3d {
             target,
             level,
@@ -337,21 +991,23 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             format,
             ty,
             opt_data,
-        } => simple!(
-            locals:
-                tex_image_3d(
-                    target,
-                    level,
-                    internal_format,
-                    width,
-                    height,
-                    depth,
-                    border,
-                    format,
-                    ty,
-                    opt_data,
-                )
-        ),
+        } => replay_upload(gl, opt_data.is_some(), || {
+            simple!(
+                locals:
+                    tex_image_3d(
+                        target,
+                        level,
+                        internal_format,
+                        width,
+                        height,
+                        depth,
+                        border,
+                        format,
+                        ty,
+                        opt_data,
+                    )
+            )
+        }),
         tex_parameter_f {
             target,
             pname,
@@ -378,22 +1034,24 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             format,
             ty,
             data,
-        } => simple!(
-            locals:
-                tex_sub_image_3d(
-                    target,
-                    level,
-                    xoffset,
-                    yoffset,
-                    zoffset,
-                    width,
-                    height,
-                    depth,
-                    format,
-                    ty,
-                    data,
-                )
-        ),
+        } => replay_upload(gl, true, || {
+            simple!(
+                locals:
+                    tex_sub_image_3d(
+                        target,
+                        level,
+                        xoffset,
+                        yoffset,
+                        zoffset,
+                        width,
+                        height,
+                        depth,
+                        format,
+                        ty,
+                        data,
+                    )
+            )
+        }),
         use_program { program } => {
             gl.use_program(program);
         }
@@ -428,7 +1086,11 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             gl.viewport(x, y, width, height);
         }
         bind_vertex_array_apple { vao } => {
-            gl.bind_vertex_array_apple(vao);
+            if locals.has_extension("GL_APPLE_vertex_array_object") {
+                gl.bind_vertex_array_apple(vao);
+            } else {
+                gl.bind_vertex_array(vao);
+            }
         }
         bind_renderbuffer {
             target,
@@ -527,17 +1189,20 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             ty,
             offset,
         } => {
-            gl.tex_sub_image_2d_pbo(
-                target,
-                level,
-                xoffset,
-                yoffset,
-                width,
-                height,
-                format,
-                ty,
-                call_to_tex_image_data_offset(offset, locals.variable),
-            );
+            let from_client = matches!(offset, TexImageData::Buf(_));
+            replay_upload(gl, from_client, || {
+                gl.tex_sub_image_2d_pbo(
+                    target,
+                    level,
+                    xoffset,
+                    yoffset,
+                    width,
+                    height,
+                    format,
+                    ty,
+                    call_to_tex_image_data_offset(offset, locals.variable),
+                );
+            });
         }
         flush {} => {
             gl.flush();
@@ -548,14 +1213,26 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         depth_mask { flag } => {
             gl.depth_mask(flag);
         }
-        create_program { returned } => check_return_value!(locals: create_program(): returned),
+        create_program { returned } => {
+            let live = gl.create_program();
+            locals
+                .replayer
+                .names
+                .insert(Namespace::Program, &[returned], &[live]);
+        }
         create_shader {
             shader_type,
             returned,
-        } => check_return_value!(locals: create_shader(shader_type): returned),
+        } => {
+            let live = gl.create_shader(shader_type);
+            locals
+                .replayer
+                .names
+                .insert(Namespace::Program, &[returned], &[live]);
+        }
         shader_source { shader, strings } => {
             let strings = <Vec<&[u8]>>::from_call(strings, locals.variable);
-            locals.gl.shader_source(shader, &strings)
+            snapshot::shader_source(locals.gl, shader, &strings)
         }
         compile_shader { shader } => {
             gl.compile_shader(shader);
@@ -591,13 +1268,29 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         clear_stencil { s } => {
             gl.clear_stencil(s);
         }
-        get_attrib_location { program, name } => unimplemented!("get_attrib_location"), /*{ gl.get_attrib_location(program, name); }*/
-        get_frag_data_location { program, name } => unimplemented!("get_frag_data_location"), /*{ gl.get_frag_data_location(program, name); }*/
+        get_attrib_location {
+            program,
+            name,
+            returned,
+        } => check_return_value!(locals: get_attrib_location(program, name): returned),
+        get_frag_data_location {
+            program,
+            name,
+            returned,
+        } => check_return_value!(locals: get_frag_data_location(program, name): returned),
         get_uniform_location {
             program,
             name,
             returned,
-        } => check_return_value!(locals: get_uniform_location(program, name): returned),
+        } => {
+            let live = simple!(locals: get_uniform_location(program, name));
+            if returned != -1 {
+                locals
+                    .replayer
+                    .names
+                    .insert_location(program, returned, live);
+            }
+        }
         get_program_iv {
             program,
             pname,
@@ -606,22 +1299,22 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         uniform_1i { location, v0 } => {
             gl.uniform_1i(location, v0);
         }
-        uniform_1iv { location, values } => unimplemented!("uniform_1iv"), /*{ gl.uniform_1iv(location, values); }*/
+        uniform_1iv { location, values } => simple!(locals: uniform_1iv(location, values)),
         uniform_1f { location, v0 } => {
             gl.uniform_1f(location, v0);
         }
-        uniform_1fv { location, values } => unimplemented!("uniform_1fv"), /*{ gl.uniform_1fv(location, values); }*/
+        uniform_1fv { location, values } => simple!(locals: uniform_1fv(location, values)),
         uniform_1ui { location, v0 } => {
             gl.uniform_1ui(location, v0);
         }
         uniform_2f { location, v0, v1 } => {
             gl.uniform_2f(location, v0, v1);
         }
-        uniform_2fv { location, values } => unimplemented!("uniform_2fv"), /*{ gl.uniform_2fv(location, values); }*/
+        uniform_2fv { location, values } => simple!(locals: uniform_2fv(location, values)),
         uniform_2i { location, v0, v1 } => {
             gl.uniform_2i(location, v0, v1);
         }
-        uniform_2iv { location, values } => unimplemented!("uniform_2iv"), /*{ gl.uniform_2iv(location, values); }*/
+        uniform_2iv { location, values } => simple!(locals: uniform_2iv(location, values)),
         uniform_2ui { location, v0, v1 } => {
             gl.uniform_2ui(location, v0, v1);
         }
@@ -633,7 +1326,7 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         } => {
             gl.uniform_3f(location, v0, v1, v2);
         }
-        uniform_3fv { location, values } => unimplemented!("uniform_3fv"), /*{ gl.uniform_3fv(location, values); }*/
+        uniform_3fv { location, values } => simple!(locals: uniform_3fv(location, values)),
         uniform_3i {
             location,
             v0,
@@ -642,7 +1335,7 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         } => {
             gl.uniform_3i(location, v0, v1, v2);
         }
-        uniform_3iv { location, values } => unimplemented!("uniform_3iv"), /*{ gl.uniform_3iv(location, values); }*/
+        uniform_3iv { location, values } => simple!(locals: uniform_3iv(location, values)),
         uniform_3ui {
             location,
             v0,
@@ -669,7 +1362,7 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         } => {
             gl.uniform_4i(location, x, y, z, w);
         }
-        uniform_4iv { location, values } => unimplemented!("uniform_4iv"), /*{ gl.uniform_4iv(location, values); }*/
+        uniform_4iv { location, values } => simple!(locals: uniform_4iv(location, values)),
         uniform_4ui {
             location,
             x,
@@ -679,24 +1372,22 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         } => {
             gl.uniform_4ui(location, x, y, z, w);
         }
-        uniform_4fv { location, values } => {
-            check_filled_slice!(locals: uniform_4fv(location): values)
-        }
+        uniform_4fv { location, values } => simple!(locals: uniform_4fv(location, values)),
         uniform_matrix_2fv {
             location,
             transpose,
             value,
-        } => check_filled_slice!(locals: uniform_matrix_4fv(location, transpose): value),
+        } => simple!(locals: uniform_matrix_2fv(location, transpose, value)),
         uniform_matrix_3fv {
             location,
             transpose,
             value,
-        } => check_filled_slice!(locals: uniform_matrix_3fv(location, transpose): value),
+        } => simple!(locals: uniform_matrix_3fv(location, transpose, value)),
         uniform_matrix_4fv {
             location,
             transpose,
             value,
-        } => check_filled_slice!(locals: uniform_matrix_4fv(location, transpose): value),
+        } => simple!(locals: uniform_matrix_4fv(location, transpose, value)),
         depth_range { near, far } => {
             gl.depth_range(near, far);
         }
@@ -758,20 +1449,22 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             yoffset,
             width,
             height,
-        } => unimplemented!("invalidate_sub_framebuffer"), /*{ gl.invalidate_sub_framebuffer(
-        target,
-        attachments,
-        xoffset,
-        yoffset,
-        width,
-        height,
-        ); }*/
+        } => simple!(
+            locals:
+                invalidate_sub_framebuffer(target, attachments, xoffset, yoffset, width, height)
+        ),
         read_buffer { mode } => {
             gl.read_buffer(mode);
         }
         read_pixels_into_buffer { x, y, pixels } => {
             let pixels = Pixels::from_call(pixels, locals.variable);
-            assert_eq!(pixels.depth, 1);
+            if pixels.depth != 1 {
+                return locals.diverged(
+                    "read_pixels_into_buffer",
+                    "a single layer of pixels".to_string(),
+                    format!("{} layers", pixels.depth),
+                );
+            }
             let expected = pixels.bytes.as_ref();
             let mut actual = expected.to_owned();
             gl.read_pixels_into_buffer(
@@ -783,17 +1476,13 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
                 pixels.pixel_type,
                 &mut actual,
             );
-            if expected != &actual[..] {
-                eprintln!("gl-replay: method read_pixels_into_buffer (serial {}) returned unexpected value",
-                          locals.serial);
+            if expected != &actual[..] && !locals.replayer.skipped_rendering {
                 let actual = Pixels {
                     bytes: std::borrow::Cow::from(actual),
                     ..pixels
                 };
-                pixels.write_image("expected.png");
-                actual.write_image("actual.png");
-                eprintln!("Comparison images saved to 'expected.png' and 'actual.png'");
-                panic!("replay cannot proceed");
+                let (expected, actual) = save_comparison_images(locals.serial, &pixels, &actual);
+                locals.diverged("read_pixels_into_buffer", expected, actual)?;
             }
         }
         read_pixels {
@@ -804,9 +1493,17 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             format,
             pixel_type,
             returned,
-        } => check_returned_vector!(
-            locals: read_pixels(x, y, width, height, format, pixel_type): returned
-        ),
+        } => {
+            let actual = gl.read_pixels(x, y, width, height, format, pixel_type);
+            let expected = get_slice(returned, locals.variable);
+            if expected != &actual[..] && !locals.replayer.skipped_rendering {
+                locals.diverged(
+                    "read_pixels",
+                    describe_slice(expected),
+                    describe_slice(&actual),
+                )?;
+            }
+        }
         read_pixels_into_pbo {
             x,
             y,
@@ -825,46 +1522,63 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         }
         begin_query { target, id } => {
             gl.begin_query(target, id);
+            locals.replayer.query_targets.insert(id, target);
         }
         end_query { target } => {
             gl.end_query(target);
         }
         query_counter { id, target } => {
             gl.query_counter(id, target);
+            locals.replayer.query_targets.insert(id, target);
         }
         get_query_object_iv {
             id,
             pname,
             returned,
-        } => unimplemented!("get_query_object_iv"),
+        } => check_query_result!(locals: get_query_object_iv(id, pname): returned),
         get_query_object_uiv {
             id,
             pname,
             returned,
-        } => unimplemented!("get_query_object_uiv"),
+        } => check_query_result!(locals: get_query_object_uiv(id, pname): returned),
         get_query_object_i64v {
             id,
             pname,
             returned,
-        } => unimplemented!("get_query_object_i64v"),
+        } => check_query_result!(locals: get_query_object_i64v(id, pname): returned),
         get_query_object_ui64v {
             id,
             pname,
             returned,
-        } => unimplemented!("get_query_object_ui64v"),
-        delete_queries { queries } => simple!(locals: delete_queries(queries)),
+        } => check_query_result!(locals: get_query_object_ui64v(id, pname): returned),
+        delete_queries { queries } => {
+            gl.delete_queries(&locals.live_names(Namespace::Query, queries));
+        }
         delete_vertex_arrays { vertex_arrays } => {
-            simple!(locals: delete_vertex_arrays(vertex_arrays))
+            gl.delete_vertex_arrays(&locals.live_names(Namespace::VertexArray, vertex_arrays));
         }
         delete_vertex_arrays_apple { vertex_arrays } => {
-            simple!(locals: delete_vertex_arrays_apple(vertex_arrays))
+            let vertex_arrays = locals.live_names(Namespace::VertexArrayApple, vertex_arrays);
+            if locals.has_extension("GL_APPLE_vertex_array_object") {
+                gl.delete_vertex_arrays_apple(&vertex_arrays);
+            } else {
+                gl.delete_vertex_arrays(&vertex_arrays);
+            }
+        }
+        delete_buffers { buffers } => {
+            gl.delete_buffers(&locals.live_names(Namespace::Buffer, buffers));
         }
-        delete_buffers { buffers } => simple!(locals: delete_buffers(buffers)),
         delete_renderbuffers { renderbuffers } => {
-            simple!(locals: delete_renderbuffers(renderbuffers))
+            gl.delete_renderbuffers(&locals.live_names(Namespace::Renderbuffer, renderbuffers));
+        }
+        delete_framebuffers { framebuffers } => {
+            gl.delete_framebuffers(&locals.live_names(Namespace::Framebuffer, framebuffers));
+        }
+        delete_textures { textures } => {
+            let textures = locals.live_names(Namespace::Texture, textures);
+            locals.replayer.texture_sizes.forget(&textures);
+            gl.delete_textures(&textures);
         }
-        delete_framebuffers { framebuffers } => simple!(locals: delete_framebuffers(framebuffers)),
-        delete_textures { textures } => simple!(locals: delete_textures(textures)),
         delete_program { program } => simple!(locals: delete_program(program)),
         tex_sub_image_3d_pbo {
             target,
@@ -879,19 +1593,22 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             ty,
             offset,
         } => {
-            gl.tex_sub_image_3d_pbo(
-                target,
-                level,
-                xoffset,
-                yoffset,
-                zoffset,
-                width,
-                height,
-                depth,
-                format,
-                ty,
-                call_to_tex_image_data_offset(offset, locals.variable),
-            );
+            let from_client = matches!(offset, TexImageData::Buf(_));
+            replay_upload(gl, from_client, || {
+                gl.tex_sub_image_3d_pbo(
+                    target,
+                    level,
+                    xoffset,
+                    yoffset,
+                    zoffset,
+                    width,
+                    height,
+                    depth,
+                    format,
+                    ty,
+                    call_to_tex_image_data_offset(offset, locals.variable),
+                );
+            });
         }
         tex_storage_2d {
             target,
@@ -918,17 +1635,39 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
             format,
             ty,
             output,
-        } => unimplemented!("get_tex_image_into_buffer"), /*{ gl.get_tex_image_into_buffer(
-        target,
-        level,
-        format,
-        ty,
-        output,
-        ); }*/
-        copy_image_sub_data {
-            src_name,
-            src_target,
-            src_level,
+        } => {
+            let expected = get_slice(output, locals.variable);
+            let mut actual = expected.to_owned();
+            gl.get_tex_image_into_buffer(target, level, format, ty, &mut actual);
+            if expected != &actual[..] && !locals.replayer.skipped_rendering {
+                // We can only save comparison images if we know the level's
+                // size.
+                let size = locals.replayer.texture_sizes.bound_level(target, level);
+                let (expected, actual) = match size {
+                    Some(size) => {
+                        let expected = Pixels {
+                            width: size.width as usize,
+                            height: size.height as usize,
+                            depth: size.depth as usize,
+                            format,
+                            pixel_type: ty,
+                            bytes: std::borrow::Cow::from(expected),
+                        };
+                        let actual = Pixels {
+                            bytes: std::borrow::Cow::from(actual),
+                            ..expected
+                        };
+                        save_comparison_images(locals.serial, &expected, &actual)
+                    }
+                    None => (describe_slice(expected), describe_slice(&actual)),
+                };
+                locals.diverged("get_tex_image_into_buffer", expected, actual)?;
+            }
+        }
+        copy_image_sub_data {
+            src_name,
+            src_target,
+            src_level,
             src_x,
             src_y,
             src_z,
@@ -950,5 +1689,826 @@ fn replay_one_with_locals(locals: &Locals, call: &Call) {
         generate_mipmap { target } => {
             gl.generate_mipmap(target);
         }
+        stencil_mask { mask } => {
+            gl.stencil_mask(mask);
+        }
+        stencil_mask_separate { face, mask } => {
+            gl.stencil_mask_separate(face, mask);
+        }
+        stencil_func { func, ref_, mask } => {
+            gl.stencil_func(func, ref_, mask);
+        }
+        stencil_func_separate {
+            face,
+            func,
+            ref_,
+            mask,
+        } => {
+            gl.stencil_func_separate(face, func, ref_, mask);
+        }
+        stencil_op {
+            sfail,
+            dpfail,
+            dppass,
+        } => {
+            gl.stencil_op(sfail, dpfail, dppass);
+        }
+        stencil_op_separate {
+            face,
+            sfail,
+            dpfail,
+            dppass,
+        } => {
+            gl.stencil_op_separate(face, sfail, dpfail, dppass);
+        }
+        draw_arrays { mode, first, count } => {
+            gl.draw_arrays(mode, first, count);
+        }
+        draw_arrays_instanced {
+            mode,
+            first,
+            count,
+            primcount,
+        } => {
+            gl.draw_arrays_instanced(mode, first, count, primcount);
+        }
+        draw_elements {
+            mode,
+            count,
+            element_type,
+            indices_offset,
+        } => {
+            gl.draw_elements(mode, count, element_type, indices_offset);
+        }
+        // We don't actually map the buffer until we replay the matching
+        // `unmap_buffer` call, which records the mapped range and whatever the
+        // application wrote to it.
+        map_buffer { target, access } => {}
+        map_buffer_range {
+            target,
+            offset,
+            length,
+            access,
+        } => {}
+        unmap_buffer {
+            target,
+            offset,
+            length,
+            access,
+            data,
+            untracked,
+            returned,
+        } => {
+            if untracked {
+                locals.diverged(
+                    "unmap_buffer",
+                    "the data written through the mapping".to_string(),
+                    format!(
+                        "nothing; the recorder can't track mappings of buffer target 0x{:x}",
+                        target
+                    ),
+                )?;
+            }
+            // A zero length means the recorder never saw a successful mapping.
+            if length != 0 {
+                let address = gl.map_buffer_range(target, offset, length, access);
+                if let Some(data) = data {
+                    let data = get_slice(data, locals.variable);
+                    if address.is_null() {
+                        locals.diverged(
+                            "map_buffer_range",
+                            "a mapped buffer".to_string(),
+                            "null".to_string(),
+                        )?;
+                    } else {
+                        unsafe {
+                            std::ptr::copy_nonoverlapping(
+                                data.as_ptr(),
+                                address as *mut u8,
+                                data.len(),
+                            );
+                        }
+                    }
+                }
+            }
+            check_return_value!(locals: unmap_buffer(target): returned)
+        }
+        buffer_sub_data_untyped {
+            target,
+            offset,
+            size_data,
+        } => {
+            let size_data = get_slice(size_data, locals.variable);
+            gl.buffer_sub_data_untyped(
+                target,
+                offset,
+                size_data.len() as GLsizeiptr,
+                size_data.as_ptr() as *const GLvoid,
+            )
+        }
+        compressed_tex_image_2d {
+            target,
+            level,
+            internal_format,
+            width,
+            height,
+            border,
+            data,
+            image_size,
+        } => {
+            gl.compressed_tex_image_2d(
+                target,
+                level,
+                internal_format,
+                width,
+                height,
+                border,
+                call_to_compressed_data(data, image_size, locals.variable),
+            );
+        }
+        compressed_tex_sub_image_2d {
+            target,
+            level,
+            xoffset,
+            yoffset,
+            width,
+            height,
+            format,
+            data,
+            image_size,
+        } => {
+            gl.compressed_tex_sub_image_2d(
+                target,
+                level,
+                xoffset,
+                yoffset,
+                width,
+                height,
+                format,
+                call_to_compressed_data(data, image_size, locals.variable),
+            );
+        }
+        copy_tex_image_2d {
+            target,
+            level,
+            internal_format,
+            x,
+            y,
+            width,
+            height,
+            border,
+        } => {
+            gl.copy_tex_image_2d(target, level, internal_format, x, y, width, height, border);
+        }
+        copy_tex_sub_image_2d {
+            target,
+            level,
+            xoffset,
+            yoffset,
+            x,
+            y,
+            width,
+            height,
+        } => {
+            gl.copy_tex_sub_image_2d(target, level, xoffset, yoffset, x, y, width, height);
+        }
+        copy_tex_sub_image_3d {
+            target,
+            level,
+            xoffset,
+            yoffset,
+            zoffset,
+            x,
+            y,
+            width,
+            height,
+        } => {
+            gl.copy_tex_sub_image_3d(
+                target, level, xoffset, yoffset, zoffset, x, y, width, height,
+            );
+        }
+        bind_buffer_base {
+            target,
+            index,
+            buffer,
+        } => {
+            gl.bind_buffer_base(target, index, buffer);
+        }
+        bind_buffer_range {
+            target,
+            index,
+            buffer,
+            offset,
+            size,
+        } => {
+            gl.bind_buffer_range(target, index, buffer, offset, size);
+        }
+        uniform_block_binding {
+            program,
+            uniform_block_index,
+            uniform_block_binding,
+        } => {
+            gl.uniform_block_binding(program, uniform_block_index, uniform_block_binding);
+        }
+        get_uniform_block_index {
+            program,
+            name,
+            returned,
+        } => check_return_value!(locals: get_uniform_block_index(program, name): returned),
+        get_uniform_indices {
+            program,
+            names,
+            returned,
+        } => {
+            let names: Vec<&str> = get_parameter(names, locals.variable);
+            let names = &names[..];
+            check_returned_vector!(locals: get_uniform_indices(program, names): returned)
+        }
+        get_active_uniform_block_i {
+            program,
+            index,
+            pname,
+            returned,
+        } => {
+            check_return_value!(locals: get_active_uniform_block_i(program, index, pname): returned)
+        }
+        get_active_uniform_block_iv {
+            program,
+            index,
+            pname,
+            returned,
+        } => check_returned_vector!(
+            locals: get_active_uniform_block_iv(program, index, pname): returned
+        ),
+        get_active_uniform_block_name {
+            program,
+            index,
+            returned,
+        } => check_return_value!(
+            locals: get_active_uniform_block_name(program, index): returned as &str
+        ),
+        get_active_attrib {
+            program,
+            index,
+            returned,
+        } => check_return_value!(
+            locals: get_active_attrib(program, index): returned as (i32, u32, String)
+        ),
+        get_active_uniform {
+            program,
+            index,
+            returned,
+        } => check_return_value!(
+            locals: get_active_uniform(program, index): returned as (i32, u32, String)
+        ),
+        get_active_uniforms_iv {
+            program,
+            indices,
+            pname,
+            returned,
+        } => {
+            let indices: Vec<GLuint> = get_parameter(indices, locals.variable);
+            check_returned_vector!(locals: get_active_uniforms_iv(program, indices, pname): returned)
+        }
+        get_program_info_log { program, returned } => {
+            check_return_value!(locals: get_program_info_log(program): returned as &str)
+        }
+        get_shader_precision_format {
+            shader_type,
+            precision_type,
+            returned,
+        } => check_return_value!(
+            locals: get_shader_precision_format(shader_type, precision_type): returned
+        ),
+        get_program_binary { program, returned } => {
+            // Binaries are specific to the implementation that produced them,
+            // so there's nothing meaningful to compare the result against.
+            gl.get_program_binary(program);
+        }
+        program_binary {
+            program,
+            format,
+            binary,
+        } => {
+            let binary = get_slice(binary, locals.variable);
+            gl.program_binary(program, format, binary);
+            if let Some(program_sources) = &locals.replayer.program_sources {
+                if !relink::link_succeeded(gl, program)
+                    && !program_sources.relink(gl, locals.variable, program, binary)
+                {
+                    locals.diverged(
+                        "program_binary",
+                        "binary accepted".to_string(),
+                        "binary rejected, and the recording has no sources for it".to_string(),
+                    )?;
+                }
+            }
+        }
+        program_parameter_i {
+            program,
+            pname,
+            value,
+        } => {
+            gl.program_parameter_i(program, pname, value);
+        }
+        get_vertex_attrib_iv {
+            index,
+            pname,
+            result,
+        } => {
+            if pname == gleam::gl::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING {
+                // The result is a buffer name, so compare it as a recorded name.
+                let expected = get_slice(result, locals.variable);
+                let mut actual = expected.to_owned();
+                unsafe {
+                    gl.get_vertex_attrib_iv(index, pname, &mut actual);
+                }
+                for name in &mut actual {
+                    *name = locals
+                        .replayer
+                        .names
+                        .recorded(Namespace::Buffer, *name as GLuint)
+                        as GLint;
+                }
+                if expected != &actual[..] {
+                    locals.diverged(
+                        "get_vertex_attrib_iv",
+                        describe_slice(expected),
+                        describe_slice(&actual),
+                    )?;
+                }
+            } else {
+                check_filled_slice!(locals: unsafe get_vertex_attrib_iv(index, pname) : result)
+            }
+        }
+        get_vertex_attrib_fv {
+            index,
+            pname,
+            result,
+        } => check_filled_slice!(locals: unsafe get_vertex_attrib_fv(index, pname) : result),
+        get_vertex_attrib_pointer_v {
+            index,
+            pname,
+            returned,
+        } => {
+            // If there's a buffer bound to the attribute, the pointer is an
+            // offset into it, which should match. Otherwise, it's an address
+            // in the recorded process, which means nothing to us.
+            let mut bound_buffer = [0];
+            unsafe {
+                gl.get_vertex_attrib_iv(
+                    index,
+                    gleam::gl::VERTEX_ATTRIB_ARRAY_BUFFER_BINDING,
+                    &mut bound_buffer,
+                );
+            }
+            if bound_buffer[0] != 0 {
+                check_return_value!(locals: get_vertex_attrib_pointer_v(index, pname): returned);
+            } else {
+                gl.get_vertex_attrib_pointer_v(index, pname);
+            }
+        }
+        get_buffer_parameter_iv {
+            target,
+            pname,
+            returned,
+        } => check_return_value!(locals: get_buffer_parameter_iv(target, pname): returned),
+        get_framebuffer_attachment_parameter_iv {
+            target,
+            attachment,
+            pname,
+            returned,
+        } => {
+            if pname == gleam::gl::FRAMEBUFFER_ATTACHMENT_OBJECT_NAME {
+                // The result is a texture or renderbuffer name, so compare it
+                // as a recorded name.
+                let live = gl.get_framebuffer_attachment_parameter_iv(target, attachment, pname);
+                let object_type = gl.get_framebuffer_attachment_parameter_iv(
+                    target,
+                    attachment,
+                    gleam::gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+                ) as GLenum;
+                let namespace = names::image_namespace(object_type);
+                let actual = locals.replayer.names.recorded(namespace, live as GLuint) as GLint;
+                if returned != actual {
+                    locals.diverged(
+                        "get_framebuffer_attachment_parameter_iv",
+                        format!("{:?}", returned),
+                        format!("{:?}", actual),
+                    )?;
+                }
+            } else {
+                check_return_value!(
+                    locals: get_framebuffer_attachment_parameter_iv(target, attachment, pname): returned
+                )
+            }
+        }
+        get_renderbuffer_parameter_iv {
+            target,
+            pname,
+            returned,
+        } => check_return_value!(locals: get_renderbuffer_parameter_iv(target, pname): returned),
+        get_tex_parameter_iv {
+            target,
+            name,
+            returned,
+        } => check_return_value!(locals: get_tex_parameter_iv(target, name): returned),
+        get_tex_parameter_fv {
+            target,
+            name,
+            returned,
+        } => check_return_value!(locals: get_tex_parameter_fv(target, name): returned),
+        insert_event_marker_ext { message } => simple!(locals: insert_event_marker_ext(message)),
+        push_group_marker_ext { message } => simple!(locals: push_group_marker_ext(message)),
+        pop_group_marker_ext {} => {
+            gl.pop_group_marker_ext();
+        }
+        debug_message_insert_khr {
+            source,
+            type_,
+            id,
+            severity,
+            message,
+        } => simple!(locals: debug_message_insert_khr(source, type_, id, severity, message)),
+        push_debug_group_khr {
+            source,
+            id,
+            message,
+        } => simple!(locals: push_debug_group_khr(source, id, message)),
+        pop_debug_group_khr {} => {
+            gl.pop_debug_group_khr();
+        }
+        fence_sync {
+            condition,
+            flags,
+            returned,
+        } => {
+            let sync = gl.fence_sync(condition, flags);
+            if returned != 0 {
+                locals.replayer.syncs.insert(returned, sync);
+            }
+        }
+        client_wait_sync {
+            sync,
+            flags,
+            timeout,
+        } => {
+            // The `Gl` trait doesn't report whether the wait timed out, so
+            // there is no result to check.
+            if let Some(sync) = locals.sync("client_wait_sync", sync)? {
+                gl.client_wait_sync(sync, flags, timeout);
+            }
+        }
+        wait_sync {
+            sync,
+            flags,
+            timeout,
+        } => {
+            if let Some(sync) = locals.sync("wait_sync", sync)? {
+                gl.wait_sync(sync, flags, timeout);
+            }
+        }
+        delete_sync { sync } => {
+            if let Some(live) = locals.sync("delete_sync", sync)? {
+                gl.delete_sync(live);
+                locals.replayer.syncs.remove(&sync);
+            }
+        }
+        texture_range_apple { target, length } => {
+            // This only tells the driver where the recorded application kept
+            // client storage for textures. It has no bearing on our memory.
+        }
+        // Apple fences only make the application wait for the GPU, and have
+        // no effect on rendering, so skip them if the extension isn't
+        // available. Whether a fence has been reached depends on timing, so
+        // there's no point checking test results.
+        gen_fences_apple { n, returned } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                locals.generate(Namespace::FenceApple, returned, |gl| gl.gen_fences_apple(n))
+            }
+        }
+        delete_fences_apple { fences } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                gl.delete_fences_apple(&locals.live_names(Namespace::FenceApple, fences));
+            }
+        }
+        set_fence_apple { fence } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                gl.set_fence_apple(fence);
+            }
+        }
+        finish_fence_apple { fence } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                gl.finish_fence_apple(fence);
+            }
+        }
+        test_fence_apple { fence } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                gl.test_fence_apple(fence);
+            }
+        }
+        test_object_apple {
+            object,
+            name,
+            returned,
+        } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                gl.test_object_apple(object, name);
+            }
+        }
+        finish_object_apple { object, name } => {
+            if locals.has_extension("GL_APPLE_fence") {
+                gl.finish_object_apple(object, name);
+            }
+        }
+        egl_image_target_texture2d_oes { target, pixels } => match pixels {
+            Some(pixels) => {
+                let pixels = Pixels::from_call(pixels, locals.variable);
+                snapshot::upload_texture(gl, target, &pixels);
+                let (width, height) = (pixels.width as GLsizei, pixels.height as GLsizei);
+                locals
+                    .replayer
+                    .texture_sizes
+                    .allocate_bound(target, 0, width, height, 1);
+            }
+            None => locals.missing_egl_image("egl_image_target_texture2d_oes")?,
+        },
+        egl_image_target_renderbuffer_storage_oes { target, pixels } => match pixels {
+            Some(pixels) => {
+                let pixels = Pixels::from_call(pixels, locals.variable);
+                snapshot::upload_renderbuffer(gl, target, &pixels);
+            }
+            None => locals.missing_egl_image("egl_image_target_renderbuffer_storage_oes")?,
+        },
+        copy_texture_chromium {
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            internal_format,
+            dest_type,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha,
+        } => {
+            if locals.has_extension("GL_CHROMIUM_copy_texture") {
+                gl.copy_texture_chromium(
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    internal_format,
+                    dest_type,
+                    unpack_flip_y,
+                    unpack_premultiply_alpha,
+                    unpack_unmultiply_alpha,
+                );
+            } else {
+                let copy = copy_texture::TextureCopy {
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    source_offset: [0, 0, 0],
+                    dest_offset: [0, 0, 0],
+                    size: None,
+                    flip_y: unpack_flip_y != 0,
+                    premultiply_alpha: unpack_premultiply_alpha != 0,
+                    unmultiply_alpha: unpack_unmultiply_alpha != 0,
+                };
+                locals.emulate_copy(
+                    "copy_texture_chromium",
+                    copy,
+                    Some((internal_format, dest_type)),
+                )?;
+            }
+        }
+        copy_sub_texture_chromium {
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            x_offset,
+            y_offset,
+            x,
+            y,
+            width,
+            height,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha,
+        } => {
+            if locals.has_extension("GL_CHROMIUM_copy_texture") {
+                gl.copy_sub_texture_chromium(
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    x_offset,
+                    y_offset,
+                    x,
+                    y,
+                    width,
+                    height,
+                    unpack_flip_y,
+                    unpack_premultiply_alpha,
+                    unpack_unmultiply_alpha,
+                );
+            } else {
+                let copy = copy_texture::TextureCopy {
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    source_offset: [x, y, 0],
+                    dest_offset: [x_offset, y_offset, 0],
+                    size: Some([width, height, 1]),
+                    flip_y: unpack_flip_y != 0,
+                    premultiply_alpha: unpack_premultiply_alpha != 0,
+                    unmultiply_alpha: unpack_unmultiply_alpha != 0,
+                };
+                locals.emulate_copy("copy_sub_texture_chromium", copy, None)?;
+            }
+        }
+        copy_texture_3d_angle {
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            internal_format,
+            dest_type,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha,
+        } => {
+            if locals.has_extension("GL_ANGLE_copy_texture_3d") {
+                gl.copy_texture_3d_angle(
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    internal_format,
+                    dest_type,
+                    unpack_flip_y,
+                    unpack_premultiply_alpha,
+                    unpack_unmultiply_alpha,
+                );
+            } else {
+                let copy = copy_texture::TextureCopy {
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    source_offset: [0, 0, 0],
+                    dest_offset: [0, 0, 0],
+                    size: None,
+                    flip_y: unpack_flip_y != 0,
+                    premultiply_alpha: unpack_premultiply_alpha != 0,
+                    unmultiply_alpha: unpack_unmultiply_alpha != 0,
+                };
+                locals.emulate_copy(
+                    "copy_texture_3d_angle",
+                    copy,
+                    Some((internal_format, dest_type)),
+                )?;
+            }
+        }
+        copy_sub_texture_3d_angle {
+            source_id,
+            source_level,
+            dest_target,
+            dest_id,
+            dest_level,
+            x_offset,
+            y_offset,
+            z_offset,
+            x,
+            y,
+            z,
+            width,
+            height,
+            depth,
+            unpack_flip_y,
+            unpack_premultiply_alpha,
+            unpack_unmultiply_alpha,
+        } => {
+            if locals.has_extension("GL_ANGLE_copy_texture_3d") {
+                gl.copy_sub_texture_3d_angle(
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    x_offset,
+                    y_offset,
+                    z_offset,
+                    x,
+                    y,
+                    z,
+                    width,
+                    height,
+                    depth,
+                    unpack_flip_y,
+                    unpack_premultiply_alpha,
+                    unpack_unmultiply_alpha,
+                );
+            } else {
+                let copy = copy_texture::TextureCopy {
+                    source_id,
+                    source_level,
+                    dest_target,
+                    dest_id,
+                    dest_level,
+                    source_offset: [x, y, z],
+                    dest_offset: [x_offset, y_offset, z_offset],
+                    size: Some([width, height, depth]),
+                    flip_y: unpack_flip_y != 0,
+                    premultiply_alpha: unpack_premultiply_alpha != 0,
+                    unmultiply_alpha: unpack_unmultiply_alpha != 0,
+                };
+                locals.emulate_copy("copy_sub_texture_3d_angle", copy, None)?;
+            }
+        }
+        bind_frag_data_location_indexed {
+            program,
+            color_number,
+            index,
+            name,
+        } => simple!(locals: bind_frag_data_location_indexed(program, color_number, index, name)),
+        get_frag_data_index {
+            program,
+            name,
+            returned,
+        } => check_return_value!(locals: get_frag_data_index(program, name): returned),
+        tex_sub_image_2d {
+            target,
+            level,
+            xoffset,
+            yoffset,
+            width,
+            height,
+            format,
+            ty,
+            data,
+        } => replay_upload(gl, true, || {
+            simple!(
+                locals:
+                    tex_sub_image_2d(
+                        target,
+                        level,
+                        xoffset,
+                        yoffset,
+                        width,
+                        height,
+                        format,
+                        ty,
+                        data,
+                    )
+            )
+        }),
+        draw_buffers { bufs } => simple!(locals: draw_buffers(bufs)),
+        tex_buffer {
+            target,
+            internal_format,
+            buffer,
+        } => {
+            gl.tex_buffer(target, internal_format, buffer);
+        }
+        vertex_attrib_4f { index, x, y, z, w } => {
+            gl.vertex_attrib_4f(index, x, y, z, w);
+        }
+        vertex_attrib_pointer_f32 {
+            index,
+            size,
+            normalized,
+            stride,
+            offset,
+        } => {
+            gl.vertex_attrib_pointer_f32(index, size, normalized, stride, offset);
+        }
+        validate_program { program, status } => {
+            gl.validate_program(program);
+            let mut actual = [0];
+            unsafe {
+                gl.get_program_iv(program, gleam::gl::VALIDATE_STATUS, &mut actual);
+            }
+            if actual[0] != status {
+                locals.diverged(
+                    "validate_program",
+                    format!("VALIDATE_STATUS {}", status),
+                    format!("VALIDATE_STATUS {}", actual[0]),
+                )?;
+            }
+        }
     }
+    Ok(())
 }
@@ -0,0 +1,611 @@
+//! Emulating the Chromium and ANGLE texture copying extensions.
+//!
+//! Recordings made on ANGLE often use `GL_CHROMIUM_copy_texture` and
+//! `GL_ANGLE_copy_texture_3d`, which most other implementations lack. When the
+//! replay `Gl` doesn't have them, we perform the same copies with framebuffer
+//! blits instead. Blits can flip the image, but they can't premultiply or
+//! unmultiply alpha, so copies that ask for that go through the CPU.
+//!
+//! The non-`sub` forms of these methods size the destination to match the
+//! source, but OpenGL gives us no way to ask how big a texture is. So a
+//! `TextureSizes` watches the calls being replayed to keep track.
+//!
+//! The emulation only handles color textures. When a copy involves something
+//! else, like a depth texture, it returns `Unsupported` rather than doing a
+//! partial job.
+
+use gleam::gl::{self, GLenum, GLint, GLsizei, GLuint, Gl};
+
+use std::collections::HashMap;
+
+use super::snapshot::{get_integer, with_default_unpack_state};
+use crate::call::Call;
+
+/// The size of one level of a texture.
+#[derive(Copy, Clone, Debug)]
+pub struct TextureLevel {
+    /// The target the texture was bound to when this level was allocated.
+    pub target: GLenum,
+
+    pub width: GLsizei,
+    pub height: GLsizei,
+    pub depth: GLsizei,
+}
+
+/// Sizes of texture levels, as established by the calls replayed so far.
+pub struct TextureSizes {
+    /// The active texture unit.
+    active_unit: GLenum,
+
+    /// The texture bound to each target, indexed by `(unit, target)`.
+    bindings: HashMap<(GLenum, GLenum), GLuint>,
+
+    /// The size of each allocated texture level, indexed by `(texture, level)`.
+    levels: HashMap<(GLuint, GLint), TextureLevel>,
+}
+
+impl Default for TextureSizes {
+    fn default() -> TextureSizes {
+        TextureSizes {
+            active_unit: gl::TEXTURE0,
+            bindings: HashMap::new(),
+            levels: HashMap::new(),
+        }
+    }
+}
+
+impl TextureSizes {
+    /// Return the size of `level` of `texture`, if we know it.
+    pub fn level(&self, texture: GLuint, level: GLint) -> Option<TextureLevel> {
+        self.levels.get(&(texture, level)).cloned()
+    }
+
+    /// Return the size of `level` of the texture bound to `target`, if we know it.
+    pub fn bound_level(&self, target: GLenum, level: GLint) -> Option<TextureLevel> {
+        let texture = self.bound_texture(target)?;
+        self.level(texture, level)
+    }
+
+    /// Return the texture bound to `target`. For a cube map face, this is the
+    /// texture bound to `TEXTURE_CUBE_MAP`.
+    fn bound_texture(&self, target: GLenum) -> Option<GLuint> {
+        let target = if is_cube_map_face(target) {
+            gl::TEXTURE_CUBE_MAP
+        } else {
+            target
+        };
+        self.bindings.get(&(self.active_unit, target)).cloned()
+    }
+
+    /// Return the size of `level` of `texture`, the source of a copy. If we
+    /// don't know its size, we can't emulate the copy.
+    pub fn copy_source(&self, texture: GLuint, level: GLint) -> Result<TextureLevel, Unsupported> {
+        self.level(texture, level).ok_or_else(|| {
+            Unsupported(format!(
+                "copy from texture {} level {}, of unknown size",
+                texture, level
+            ))
+        })
+    }
+
+    /// Note that `level` of the texture bound to `target` now has the given size.
+    pub fn allocate_bound(
+        &mut self,
+        target: GLenum,
+        level: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        depth: GLsizei,
+    ) {
+        let texture = match self.bound_texture(target) {
+            Some(texture) => texture,
+            None => return,
+        };
+        self.allocate(texture, target, level, width, height, depth);
+    }
+
+    fn allocate(
+        &mut self,
+        texture: GLuint,
+        target: GLenum,
+        level: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        depth: GLsizei,
+    ) {
+        let size = TextureLevel {
+            target,
+            width,
+            height,
+            depth,
+        };
+        self.levels.insert((texture, level), size);
+    }
+
+    /// Forget the sizes of `textures`, which are being deleted.
+    ///
+    /// `observe` can't do this itself, since `delete_textures` passes the
+    /// names in variable-length data, which `Names::translate` leaves alone.
+    pub fn forget(&mut self, textures: &[GLuint]) {
+        self.levels
+            .retain(|&(texture, _), _| !textures.contains(&texture));
+    }
+
+    /// Take note of `call`, if it affects texture sizes.
+    ///
+    /// This must see every call in the recording, in order, with its object
+    /// names translated, apart from `delete_textures` calls, which must be
+    /// passed to `forget`.
+    pub fn observe(&mut self, call: &Call) {
+        use Call::*;
+        match *call {
+            active_texture { texture } => self.active_unit = texture,
+            bind_texture { target, texture } => {
+                self.bindings.insert((self.active_unit, target), texture);
+            }
+            tex_image_2d {
+                target,
+                level,
+                width,
+                height,
+                ..
+            }
+            | compressed_tex_image_2d {
+                target,
+                level,
+                width,
+                height,
+                ..
+            }
+            | copy_tex_image_2d {
+                target,
+                level,
+                width,
+                height,
+                ..
+            } => self.allocate_bound(target, level, width, height, 1),
+            tex_image_3d {
+                target,
+                level,
+                width,
+                height,
+                depth,
+                ..
+            } => self.allocate_bound(target, level, width, height, depth),
+            tex_storage_2d {
+                target,
+                levels,
+                width,
+                height,
+                ..
+            } => {
+                for level in 0..levels {
+                    let width = (width >> level).max(1);
+                    let height = (height >> level).max(1);
+                    self.allocate_bound(target, level, width, height, 1);
+                }
+            }
+            tex_storage_3d {
+                target,
+                levels,
+                width,
+                height,
+                depth,
+                ..
+            } => {
+                for level in 0..levels {
+                    let width = (width >> level).max(1);
+                    let height = (height >> level).max(1);
+                    // Only 3D textures shrink in depth; array layers don't.
+                    let depth = if target == gl::TEXTURE_3D {
+                        (depth >> level).max(1)
+                    } else {
+                        depth
+                    };
+                    self.allocate_bound(target, level, width, height, depth);
+                }
+            }
+            copy_texture_chromium {
+                source_id,
+                source_level,
+                dest_target,
+                dest_id,
+                dest_level,
+                ..
+            }
+            | copy_texture_3d_angle {
+                source_id,
+                source_level,
+                dest_target,
+                dest_id,
+                dest_level,
+                ..
+            } => {
+                if let Some(source) = self.level(source_id, source_level) {
+                    self.allocate(
+                        dest_id,
+                        dest_target,
+                        dest_level,
+                        source.width,
+                        source.height,
+                        source.depth,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A description of a copy the emulation can't perform.
+pub struct Unsupported(pub String);
+
+/// A copy between textures, as performed by the Chromium and ANGLE texture
+/// copying methods, both the `sub` forms and those that copy a whole level.
+pub struct TextureCopy {
+    pub source_id: GLuint,
+    pub source_level: GLint,
+    pub dest_target: GLenum,
+    pub dest_id: GLuint,
+    pub dest_level: GLint,
+
+    /// The `x`, `y`, and `z` coordinates of the region to copy from.
+    pub source_offset: [GLint; 3],
+
+    /// The `x`, `y`, and `z` coordinates at which to place the copy.
+    pub dest_offset: [GLint; 3],
+
+    /// The width, height, and depth of the region to copy, or `None` to copy
+    /// the whole source level.
+    pub size: Option<[GLsizei; 3]>,
+
+    pub flip_y: bool,
+    pub premultiply_alpha: bool,
+    pub unmultiply_alpha: bool,
+}
+
+impl TextureCopy {
+    /// Perform this copy using framebuffer blits. The source level has the
+    /// size `source`.
+    pub fn emulate(&self, gl: &dyn Gl, source: &TextureLevel) -> Result<(), Unsupported> {
+        let saved_read_framebuffer = get_integer(gl, gl::READ_FRAMEBUFFER_BINDING) as GLuint;
+        let saved_draw_framebuffer = get_integer(gl, gl::DRAW_FRAMEBUFFER_BINDING) as GLuint;
+        let scissor_test = gl.is_enabled(gl::SCISSOR_TEST) != 0;
+        if scissor_test {
+            gl.disable(gl::SCISSOR_TEST);
+        }
+
+        let framebuffers = gl.gen_framebuffers(2);
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, framebuffers[0]);
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, framebuffers[1]);
+
+        let size = self
+            .size
+            .unwrap_or([source.width, source.height, source.depth]);
+        let result = self.copy_layers(gl, source.target, size);
+
+        gl.bind_framebuffer(gl::READ_FRAMEBUFFER, saved_read_framebuffer);
+        gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, saved_draw_framebuffer);
+        gl.delete_framebuffers(&framebuffers);
+        if scissor_test {
+            gl.enable(gl::SCISSOR_TEST);
+        }
+        result
+    }
+
+    /// Copy each layer of a region of the given `size` from a source level
+    /// allocated on `source_target`, using the framebuffers bound to
+    /// `READ_FRAMEBUFFER` and `DRAW_FRAMEBUFFER`.
+    fn copy_layers(
+        &self,
+        gl: &dyn Gl,
+        source_target: GLenum,
+        size: [GLsizei; 3],
+    ) -> Result<(), Unsupported> {
+        // Asking for both premultiplication and unmultiplication is
+        // equivalent to asking for neither.
+        let convert_alpha = self.premultiply_alpha != self.unmultiply_alpha;
+
+        let [source_x, source_y, source_z] = self.source_offset;
+        let [dest_x, dest_y, dest_z] = self.dest_offset;
+        let [width, height, depth] = size;
+        for layer in 0..depth {
+            attach(
+                gl,
+                gl::READ_FRAMEBUFFER,
+                source_target,
+                self.source_id,
+                self.source_level,
+                source_z + layer,
+            );
+            attach(
+                gl,
+                gl::DRAW_FRAMEBUFFER,
+                self.dest_target,
+                self.dest_id,
+                self.dest_level,
+                dest_z + layer,
+            );
+            check_color_attachment(gl, gl::READ_FRAMEBUFFER, "source", convert_alpha)?;
+            check_color_attachment(gl, gl::DRAW_FRAMEBUFFER, "destination", convert_alpha)?;
+            if convert_alpha {
+                self.copy_layer_through_cpu(gl, size, dest_z + layer)?;
+                continue;
+            }
+
+            let (dest_y0, dest_y1) = if self.flip_y {
+                (dest_y + height, dest_y)
+            } else {
+                (dest_y, dest_y + height)
+            };
+            gl.blit_framebuffer(
+                source_x,
+                source_y,
+                source_x + width,
+                source_y + height,
+                dest_x,
+                dest_y0,
+                dest_x + width,
+                dest_y1,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+        }
+        Ok(())
+    }
+
+    /// Copy one layer by reading it from the current read framebuffer,
+    /// converting it, and uploading it to the destination texture.
+    fn copy_layer_through_cpu(
+        &self,
+        gl: &dyn Gl,
+        size: [GLsizei; 3],
+        dest_z: GLint,
+    ) -> Result<(), Unsupported> {
+        let [source_x, source_y, _] = self.source_offset;
+        let [dest_x, dest_y, _] = self.dest_offset;
+        let [width, height, _] = size;
+
+        let pack_buffer = get_integer(gl, gl::PIXEL_PACK_BUFFER_BINDING) as GLuint;
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, 0);
+        let mut pixels = gl.read_pixels(
+            source_x,
+            source_y,
+            width,
+            height,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+        );
+        gl.bind_buffer(gl::PIXEL_PACK_BUFFER, pack_buffer);
+
+        for pixel in pixels.chunks_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                let value = *channel as u32;
+                *channel = if self.premultiply_alpha {
+                    ((value * alpha + 127) / 255) as u8
+                } else if alpha == 0 {
+                    0
+                } else {
+                    ((value * 255 + alpha / 2) / alpha).min(255) as u8
+                };
+            }
+        }
+        if self.flip_y {
+            let row_length = width as usize * 4;
+            let rows: Vec<&[u8]> = pixels.chunks(row_length).rev().collect();
+            pixels = rows.concat();
+        }
+
+        let (bind_target, binding) = binding_for_target(self.dest_target)?;
+        let saved_texture = get_integer(gl, binding) as GLuint;
+        gl.bind_texture(bind_target, self.dest_id);
+        with_default_unpack_state(gl, || {
+            if is_layered(self.dest_target) {
+                gl.tex_sub_image_3d(
+                    self.dest_target,
+                    self.dest_level,
+                    dest_x,
+                    dest_y,
+                    dest_z,
+                    width,
+                    height,
+                    1,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    &pixels,
+                );
+            } else {
+                gl.tex_sub_image_2d(
+                    self.dest_target,
+                    self.dest_level,
+                    dest_x,
+                    dest_y,
+                    width,
+                    height,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    &pixels,
+                );
+            }
+        });
+        gl.bind_texture(bind_target, saved_texture);
+        Ok(())
+    }
+}
+
+/// Allocate `level` of `texture`, as the non-`sub` copy methods do before
+/// copying.
+pub fn allocate(
+    gl: &dyn Gl,
+    target: GLenum,
+    texture: GLuint,
+    level: GLint,
+    internal_format: GLint,
+    ty: GLenum,
+    size: &TextureLevel,
+) -> Result<(), Unsupported> {
+    let format = base_format(internal_format as GLenum)?;
+    let (bind_target, binding) = binding_for_target(target)?;
+    let saved_texture = get_integer(gl, binding) as GLuint;
+    gl.bind_texture(bind_target, texture);
+    if is_layered(target) {
+        gl.tex_image_3d(
+            target,
+            level,
+            internal_format,
+            size.width,
+            size.height,
+            size.depth,
+            0,
+            format,
+            ty,
+            None,
+        );
+    } else {
+        gl.tex_image_2d(
+            target,
+            level,
+            internal_format,
+            size.width,
+            size.height,
+            0,
+            format,
+            ty,
+            None,
+        );
+    }
+    gl.bind_texture(bind_target, saved_texture);
+    Ok(())
+}
+
+/// Attach `layer` of `level` of `texture` to `framebuffer_target`'s first
+/// color attachment.
+fn attach(
+    gl: &dyn Gl,
+    framebuffer_target: GLenum,
+    texture_target: GLenum,
+    texture: GLuint,
+    level: GLint,
+    layer: GLint,
+) {
+    if is_layered(texture_target) {
+        gl.framebuffer_texture_layer(
+            framebuffer_target,
+            gl::COLOR_ATTACHMENT0,
+            texture,
+            level,
+            layer,
+        );
+    } else {
+        gl.framebuffer_texture_2d(
+            framebuffer_target,
+            gl::COLOR_ATTACHMENT0,
+            texture_target,
+            texture,
+            level,
+        );
+    }
+}
+
+fn is_layered(target: GLenum) -> bool {
+    target == gl::TEXTURE_3D || target == gl::TEXTURE_2D_ARRAY
+}
+
+fn is_cube_map_face(target: GLenum) -> bool {
+    matches!(
+        target,
+        gl::TEXTURE_CUBE_MAP_POSITIVE_X
+            | gl::TEXTURE_CUBE_MAP_NEGATIVE_X
+            | gl::TEXTURE_CUBE_MAP_POSITIVE_Y
+            | gl::TEXTURE_CUBE_MAP_NEGATIVE_Y
+            | gl::TEXTURE_CUBE_MAP_POSITIVE_Z
+            | gl::TEXTURE_CUBE_MAP_NEGATIVE_Z
+    )
+}
+
+/// Check that the image attached to `framebuffer_target`'s first color
+/// attachment is one we can copy. If `through_cpu` is true, the copy reads or
+/// writes the image as `RGBA` `UNSIGNED_BYTE` pixels, so it must hold
+/// normalized values.
+fn check_color_attachment(
+    gl: &dyn Gl,
+    framebuffer_target: GLenum,
+    role: &str,
+    through_cpu: bool,
+) -> Result<(), Unsupported> {
+    let status = gl.check_frame_buffer_status(framebuffer_target);
+    if status != gl::FRAMEBUFFER_COMPLETE {
+        return Err(Unsupported(format!(
+            "{} texture can't be a color attachment: framebuffer status 0x{:x}",
+            role, status
+        )));
+    }
+    if through_cpu {
+        let component_type = gl.get_framebuffer_attachment_parameter_iv(
+            framebuffer_target,
+            gl::COLOR_ATTACHMENT0,
+            gl::FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE,
+        ) as GLenum;
+        if component_type != gl::UNSIGNED_NORMALIZED {
+            return Err(Unsupported(format!(
+                "alpha conversion of {} texture with component type 0x{:x}",
+                role, component_type
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Return the target to which a texture must be bound to operate on `target`,
+/// and the name of that binding for `get_integer_v`.
+fn binding_for_target(target: GLenum) -> Result<(GLenum, GLenum), Unsupported> {
+    Ok(match target {
+        gl::TEXTURE_2D => (target, gl::TEXTURE_BINDING_2D),
+        gl::TEXTURE_3D => (target, gl::TEXTURE_BINDING_3D),
+        gl::TEXTURE_2D_ARRAY => (target, gl::TEXTURE_BINDING_2D_ARRAY),
+        gl::TEXTURE_RECTANGLE => (target, gl::TEXTURE_BINDING_RECTANGLE),
+        gl::TEXTURE_EXTERNAL_OES => (target, gl::TEXTURE_BINDING_EXTERNAL_OES),
+        _ if is_cube_map_face(target) => (gl::TEXTURE_CUBE_MAP, gl::TEXTURE_BINDING_CUBE_MAP),
+        _ => {
+            return Err(Unsupported(format!(
+                "destination texture target 0x{:x}",
+                target
+            )))
+        }
+    })
+}
+
+/// Return the unsized format corresponding to `internal_format`, suitable for
+/// passing as the `format` argument of `tex_image_2d`.
+///
+/// This covers the destination formats `GL_CHROMIUM_copy_texture` accepts.
+fn base_format(internal_format: GLenum) -> Result<GLenum, Unsupported> {
+    Ok(match internal_format {
+        gl::R8 | gl::R16F | gl::R32F => gl::RED,
+        gl::RG8 | gl::RG16F | gl::RG32F => gl::RG,
+        gl::RGB8
+        | gl::SRGB8
+        | gl::RGB565
+        | gl::RGB16F
+        | gl::RGB32F
+        | gl::R11F_G11F_B10F
+        | gl::RGB9_E5 => gl::RGB,
+        gl::RGBA8 | gl::SRGB8_ALPHA8 | gl::RGBA4 | gl::RGB5_A1 | gl::RGBA16F | gl::RGBA32F => {
+            gl::RGBA
+        }
+        gl::R8UI => gl::RED_INTEGER,
+        gl::RG8UI => gl::RG_INTEGER,
+        gl::RGB8UI => gl::RGB_INTEGER,
+        gl::RGBA8UI => gl::RGBA_INTEGER,
+        gl::RGB | gl::RGBA | gl::BGRA | gl::ALPHA | gl::LUMINANCE | gl::LUMINANCE_ALPHA => {
+            internal_format
+        }
+        _ => {
+            return Err(Unsupported(format!(
+                "destination internal format 0x{:x}",
+                internal_format
+            )))
+        }
+    })
+}
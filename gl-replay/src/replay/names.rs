@@ -0,0 +1,259 @@
+//! Translating the object names in a recording to live names.
+//!
+//! OpenGL implementations are free to choose whatever names they like for the
+//! objects that `gen_textures`, `create_program` and their kin create, so the
+//! replay `Gl` may well hand out different names than the recorded application
+//! got. Similarly, `get_uniform_location` may return different locations. A
+//! `Names` table maps the names that appear in the recording to the ones the
+//! replay `Gl` actually returned, and `translate` rewrites a `Call`'s object
+//! arguments accordingly before we replay it.
+//!
+//! Names the table has never seen, including zero, translate to themselves.
+//! This covers applications that choose their own names, as well as recordings
+//! that begin after the objects they use were created.
+//!
+//! Some queries return object names, like `get_vertex_attrib_iv` with
+//! `VERTEX_ATTRIB_ARRAY_BUFFER_BINDING`. The replay translates those results
+//! back to recorded names with `recorded` before comparing them.
+
+use gleam::gl::{self, GLenum, GLint, GLuint};
+
+use std::collections::HashMap;
+
+use crate::call::Call;
+
+/// A kind of OpenGL object with its own space of names.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Namespace {
+    Buffer,
+    FenceApple,
+    Framebuffer,
+    /// Programs and shaders, which share a single namespace.
+    Program,
+    Query,
+    Renderbuffer,
+    Texture,
+    VertexArray,
+    VertexArrayApple,
+}
+
+#[derive(Default)]
+pub struct Names {
+    /// Live object names, indexed by namespace and recorded name.
+    objects: HashMap<(Namespace, GLuint), GLuint>,
+
+    /// Recorded object names, indexed by namespace and live name.
+    recorded: HashMap<(Namespace, GLuint), GLuint>,
+
+    /// Live uniform locations, indexed by live program and recorded location.
+    locations: HashMap<(GLuint, GLint), GLint>,
+
+    /// The live program most recently passed to `use_program`.
+    current_program: GLuint,
+}
+
+impl Names {
+    /// Return the live name for the object the recording calls `recorded`.
+    pub fn get(&self, namespace: Namespace, recorded: GLuint) -> GLuint {
+        match self.objects.get(&(namespace, recorded)) {
+            Some(&live) => live,
+            None => recorded,
+        }
+    }
+
+    /// Return the live names for the objects the recording calls `recorded`.
+    pub fn get_all(&self, namespace: Namespace, recorded: &[GLuint]) -> Vec<GLuint> {
+        recorded
+            .iter()
+            .map(|&name| self.get(namespace, name))
+            .collect()
+    }
+
+    /// Return the recorded name for the object the replay `Gl` calls `live`.
+    pub fn recorded(&self, namespace: Namespace, live: GLuint) -> GLuint {
+        match self.recorded.get(&(namespace, live)) {
+            Some(&recorded) => recorded,
+            None => live,
+        }
+    }
+
+    /// Note that the objects the recording calls `recorded` are named `live`.
+    ///
+    /// Deleting an object doesn't remove its entry: a recording never uses a
+    /// deleted name again until the recorded implementation hands it out anew,
+    /// at which point this replaces the stale entry.
+    pub fn insert(&mut self, namespace: Namespace, recorded: &[GLuint], live: &[GLuint]) {
+        assert_eq!(recorded.len(), live.len());
+        for (&recorded, &live) in recorded.iter().zip(live) {
+            self.objects.insert((namespace, recorded), live);
+            self.recorded.insert((namespace, live), recorded);
+        }
+    }
+
+    /// Note that in the live `program`, the uniform the recording found at
+    /// `recorded` is at `live`.
+    pub fn insert_location(&mut self, program: GLuint, recorded: GLint, live: GLint) {
+        self.locations.insert((program, recorded), live);
+    }
+
+    /// Return `call` with its object names and uniform locations translated to
+    /// live ones.
+    ///
+    /// Object names passed in variable-length data, like the arrays passed to
+    /// the `delete_*` methods, are left for the replay to translate.
+    pub fn translate(&mut self, call: &Call) -> Call {
+        use Call::*;
+        use Namespace::*;
+
+        let mut call = *call;
+        match &mut call {
+            bind_buffer { buffer, .. }
+            | bind_buffer_base { buffer, .. }
+            | bind_buffer_range { buffer, .. }
+            | tex_buffer { buffer, .. } => self.translate_name(Buffer, buffer),
+
+            bind_texture { texture, .. }
+            | is_texture { texture }
+            | framebuffer_texture_2d { texture, .. }
+            | framebuffer_texture_layer { texture, .. } => self.translate_name(Texture, texture),
+            copy_texture_chromium {
+                source_id, dest_id, ..
+            }
+            | copy_sub_texture_chromium {
+                source_id, dest_id, ..
+            }
+            | copy_texture_3d_angle {
+                source_id, dest_id, ..
+            }
+            | copy_sub_texture_3d_angle {
+                source_id, dest_id, ..
+            } => {
+                self.translate_name(Texture, source_id);
+                self.translate_name(Texture, dest_id);
+            }
+            copy_image_sub_data {
+                src_name,
+                src_target,
+                dst_name,
+                dst_target,
+                ..
+            } => {
+                self.translate_name(image_namespace(*src_target), src_name);
+                self.translate_name(image_namespace(*dst_target), dst_name);
+            }
+
+            bind_framebuffer { framebuffer, .. } | is_framebuffer { framebuffer } => {
+                self.translate_name(Framebuffer, framebuffer)
+            }
+
+            bind_renderbuffer { renderbuffer, .. }
+            | is_renderbuffer { renderbuffer }
+            | framebuffer_renderbuffer { renderbuffer, .. } => {
+                self.translate_name(Renderbuffer, renderbuffer)
+            }
+
+            begin_query { id, .. }
+            | query_counter { id, .. }
+            | get_query_object_iv { id, .. }
+            | get_query_object_uiv { id, .. }
+            | get_query_object_i64v { id, .. }
+            | get_query_object_ui64v { id, .. } => self.translate_name(Query, id),
+
+            bind_vertex_array { vao } => self.translate_name(VertexArray, vao),
+            bind_vertex_array_apple { vao } => self.translate_name(VertexArrayApple, vao),
+
+            set_fence_apple { fence }
+            | finish_fence_apple { fence }
+            | test_fence_apple { fence } => self.translate_name(FenceApple, fence),
+            test_object_apple { object, name, .. } | finish_object_apple { object, name } => {
+                match *object {
+                    gl::FENCE_APPLE => self.translate_name(FenceApple, name),
+                    gl::TEXTURE => self.translate_name(Texture, name),
+                    _ => {}
+                }
+            }
+
+            use_program { program } => {
+                self.translate_name(Program, program);
+                self.current_program = *program;
+            }
+            attach_shader { program, shader } | detach_shader { program, shader } => {
+                self.translate_name(Program, program);
+                self.translate_name(Program, shader);
+            }
+            shader_source { shader, .. }
+            | compile_shader { shader }
+            | get_shader_iv { shader, .. }
+            | delete_shader { shader }
+            | is_shader { shader } => self.translate_name(Program, shader),
+            bind_attrib_location { program, .. }
+            | link_program { program }
+            | get_attrib_location { program, .. }
+            | get_frag_data_location { program, .. }
+            | get_uniform_location { program, .. }
+            | get_program_iv { program, .. }
+            | delete_program { program }
+            | uniform_block_binding { program, .. }
+            | get_uniform_block_index { program, .. }
+            | get_uniform_indices { program, .. }
+            | get_active_uniform_block_i { program, .. }
+            | get_active_uniform_block_iv { program, .. }
+            | get_active_uniform_block_name { program, .. }
+            | get_active_attrib { program, .. }
+            | get_active_uniform { program, .. }
+            | get_active_uniforms_iv { program, .. }
+            | get_program_info_log { program, .. }
+            | get_program_binary { program, .. }
+            | program_binary { program, .. }
+            | program_parameter_i { program, .. }
+            | bind_frag_data_location_indexed { program, .. }
+            | get_frag_data_index { program, .. }
+            | validate_program { program, .. } => self.translate_name(Program, program),
+
+            uniform_1i { location, .. }
+            | uniform_1iv { location, .. }
+            | uniform_1f { location, .. }
+            | uniform_1fv { location, .. }
+            | uniform_1ui { location, .. }
+            | uniform_2f { location, .. }
+            | uniform_2fv { location, .. }
+            | uniform_2i { location, .. }
+            | uniform_2iv { location, .. }
+            | uniform_2ui { location, .. }
+            | uniform_3f { location, .. }
+            | uniform_3fv { location, .. }
+            | uniform_3i { location, .. }
+            | uniform_3iv { location, .. }
+            | uniform_3ui { location, .. }
+            | uniform_4f { location, .. }
+            | uniform_4i { location, .. }
+            | uniform_4iv { location, .. }
+            | uniform_4ui { location, .. }
+            | uniform_4fv { location, .. }
+            | uniform_matrix_2fv { location, .. }
+            | uniform_matrix_3fv { location, .. }
+            | uniform_matrix_4fv { location, .. } => {
+                if let Some(&live) = self.locations.get(&(self.current_program, *location)) {
+                    *location = live;
+                }
+            }
+
+            _ => {}
+        }
+        call
+    }
+
+    fn translate_name(&self, namespace: Namespace, name: &mut GLuint) {
+        *name = self.get(namespace, *name);
+    }
+}
+
+/// Return the namespace of an image named by `copy_image_sub_data`, given its
+/// target, or of a framebuffer attachment, given its object type.
+pub fn image_namespace(target: GLenum) -> Namespace {
+    if target == gl::RENDERBUFFER {
+        Namespace::Renderbuffer
+    } else {
+        Namespace::Texture
+    }
+}
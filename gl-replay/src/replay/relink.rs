@@ -0,0 +1,183 @@
+//! Linking programs from source when the replay `Gl` rejects a binary.
+//!
+//! Program binaries are specific to the OpenGL implementation that produced
+//! them, so a recording that loads binaries saved on one machine usually can't
+//! be replayed on another: `program_binary` simply fails, and the program is
+//! left unlinked.
+//!
+//! However, if the recording also includes the `get_program_binary` call that
+//! retrieved the binary in the first place, then we know which shader sources
+//! and attribute bindings went into it. A `SourceTracker` watches the calls
+//! being replayed to collect that information, so that a rejected binary can
+//! be replaced with a program linked from source.
+
+use gleam::gl::{self, GLenum, GLuint, Gl};
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::{get_parameter, get_slice, snapshot, Parameter};
+use crate::call::Call;
+use crate::form::{Seq, Str, Var};
+
+/// The inputs to a `link_program` call, as recorded.
+#[derive(Clone, Default)]
+struct ProgramSources {
+    /// The type and sources of each shader attached to the program.
+    shaders: Vec<(GLenum, Var<Seq<Seq<u8>>>)>,
+
+    /// The attribute locations bound for the program, as `(index, name)` pairs.
+    attrib_locations: Vec<(GLuint, Var<Str>)>,
+}
+
+#[derive(Default)]
+pub struct SourceTracker {
+    /// The type of each shader, by name.
+    shader_types: HashMap<GLuint, GLenum>,
+
+    /// The sources most recently passed to `shader_source` for each shader.
+    shader_sources: HashMap<GLuint, Var<Seq<Seq<u8>>>>,
+
+    /// The shaders currently attached to each program.
+    attached_shaders: HashMap<GLuint, Vec<GLuint>>,
+
+    /// The attribute locations bound for each program so far.
+    attrib_locations: HashMap<GLuint, Vec<(GLuint, Var<Str>)>>,
+
+    /// The sources each program was last linked from.
+    linked: HashMap<GLuint, ProgramSources>,
+
+    /// The sources of each program binary the application retrieved, indexed
+    /// by a hash of the binary's contents.
+    binaries: HashMap<u64, ProgramSources>,
+}
+
+fn hash_binary(binary: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    binary.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl SourceTracker {
+    /// Take note of `call`, if it affects how programs are linked.
+    ///
+    /// This must see every call in the recording, in order.
+    pub fn observe(&mut self, call: &Call, variable: &[u8]) {
+        use Call::*;
+        match *call {
+            create_shader {
+                shader_type,
+                returned,
+            } => {
+                self.shader_types.insert(returned, shader_type);
+            }
+            shader_source { shader, strings } => {
+                self.shader_sources.insert(shader, strings);
+            }
+            attach_shader { program, shader } => {
+                self.attached_shaders
+                    .entry(program)
+                    .or_default()
+                    .push(shader);
+            }
+            detach_shader { program, shader } => {
+                if let Some(shaders) = self.attached_shaders.get_mut(&program) {
+                    shaders.retain(|&attached| attached != shader);
+                }
+            }
+            bind_attrib_location {
+                program,
+                index,
+                name,
+            } => {
+                self.attrib_locations
+                    .entry(program)
+                    .or_default()
+                    .push((index, name));
+            }
+            link_program { program } => {
+                let shaders = self
+                    .attached_shaders
+                    .get(&program)
+                    .map(|shaders| &shaders[..])
+                    .unwrap_or(&[]);
+                let shaders = shaders
+                    .iter()
+                    .filter_map(|shader| {
+                        let shader_type = *self.shader_types.get(shader)?;
+                        let sources = *self.shader_sources.get(shader)?;
+                        Some((shader_type, sources))
+                    })
+                    .collect();
+                let attrib_locations = self
+                    .attrib_locations
+                    .get(&program)
+                    .cloned()
+                    .unwrap_or_default();
+                self.linked.insert(
+                    program,
+                    ProgramSources {
+                        shaders,
+                        attrib_locations,
+                    },
+                );
+            }
+            delete_program { program } => {
+                self.attached_shaders.remove(&program);
+                self.attrib_locations.remove(&program);
+                self.linked.remove(&program);
+            }
+            get_program_binary {
+                program,
+                returned: (binary, _format),
+            } => {
+                if let Some(sources) = self.linked.get(&program) {
+                    let binary = get_slice(binary, variable);
+                    self.binaries.insert(hash_binary(binary), sources.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Link `program` from the sources that produced `binary`.
+    ///
+    /// Return false if the recording never retrieved `binary` from a program
+    /// linked from source, so we don't know what it contains.
+    pub fn relink(&self, gl: &dyn Gl, variable: &[u8], program: GLuint, binary: &[u8]) -> bool {
+        let sources = match self.binaries.get(&hash_binary(binary)) {
+            Some(sources) => sources,
+            None => return false,
+        };
+
+        let mut shaders = vec![];
+        for &(shader_type, strings) in &sources.shaders {
+            let shader = gl.create_shader(shader_type);
+            let strings = <Vec<&[u8]>>::from_call(strings, variable);
+            snapshot::shader_source(gl, shader, &strings);
+            gl.compile_shader(shader);
+            gl.attach_shader(program, shader);
+            shaders.push(shader);
+        }
+        for &(index, name) in &sources.attrib_locations {
+            gl.bind_attrib_location(program, index, get_parameter(name, variable));
+        }
+        gl.link_program(program);
+        for shader in shaders {
+            gl.detach_shader(program, shader);
+            gl.delete_shader(shader);
+        }
+
+        true
+    }
+}
+
+/// Return true if `program` is successfully linked.
+pub fn link_succeeded(gl: &dyn Gl, program: GLuint) -> bool {
+    let mut status = [0];
+    unsafe {
+        gl.get_program_iv(program, gl::LINK_STATUS, &mut status);
+    }
+    status[0] != 0
+}
@@ -0,0 +1,302 @@
+//! Recreating EGLImage-backed storage from recorded snapshots.
+//!
+//! An EGLImage has no meaning outside the process that created it, so when
+//! the recorded application attached one to a texture or renderbuffer, the
+//! recorder read back the image's contents and recorded those instead. Here
+//! we recreate that storage as ordinary texture or renderbuffer contents.
+//!
+//! Textures made from EGLImages are often bound to `TEXTURE_EXTERNAL_OES`,
+//! which can't be given ordinary storage, so the replay treats that target as
+//! `TEXTURE_2D` throughout, and has shaders sample external textures as 2D
+//! textures.
+
+use gleam::gl::{self, GLenum, GLint, GLsizei, GLuint, Gl};
+
+use std::borrow::Cow;
+
+use crate::call::Call;
+use crate::pixels::Pixels;
+
+pub fn get_integer(gl: &dyn Gl, name: GLenum) -> GLint {
+    let mut value = [0];
+    unsafe {
+        gl.get_integer_v(name, &mut value);
+    }
+    value[0]
+}
+
+/// Call `f` with pixel unpacking state reset to its defaults.
+///
+/// This makes uploads take pixels from our slices exactly as they are,
+/// regardless of the unpacking state the application has established.
+pub fn with_default_unpack_state<F: FnOnce()>(gl: &dyn Gl, f: F) {
+    let defaults = [
+        (gl::UNPACK_ROW_LENGTH, 0),
+        (gl::UNPACK_IMAGE_HEIGHT, 0),
+        (gl::UNPACK_SKIP_PIXELS, 0),
+        (gl::UNPACK_SKIP_ROWS, 0),
+        (gl::UNPACK_SKIP_IMAGES, 0),
+        (gl::UNPACK_ALIGNMENT, 4),
+    ];
+    let saved: Vec<_> = defaults
+        .iter()
+        .map(|&(name, _)| (name, get_integer(gl, name)))
+        .collect();
+    let unpack_buffer = get_integer(gl, gl::PIXEL_UNPACK_BUFFER_BINDING) as GLuint;
+    for &(name, value) in &defaults {
+        gl.pixel_store_i(name, value);
+    }
+    gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, 0);
+
+    f();
+
+    gl.bind_buffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer);
+    for (name, value) in saved {
+        gl.pixel_store_i(name, value);
+    }
+}
+
+/// Call `f` with the pixel unpacking skip state cleared.
+pub fn with_unpack_skips_cleared<F: FnOnce()>(gl: &dyn Gl, f: F) {
+    let names = [
+        gl::UNPACK_SKIP_PIXELS,
+        gl::UNPACK_SKIP_ROWS,
+        gl::UNPACK_SKIP_IMAGES,
+    ];
+    let saved: Vec<_> = names
+        .iter()
+        .map(|&name| (name, get_integer(gl, name)))
+        .collect();
+    for &(name, value) in &saved {
+        if value != 0 {
+            gl.pixel_store_i(name, 0);
+        }
+    }
+
+    f();
+
+    for (name, value) in saved {
+        if value != 0 {
+            gl.pixel_store_i(name, value);
+        }
+    }
+}
+
+/// Return the sized internal format for storing `pixels`, as read back by the
+/// recorder.
+fn internal_format(pixels: &Pixels) -> GLenum {
+    if pixels.pixel_type == gl::FLOAT {
+        gl::RGBA32F
+    } else {
+        gl::RGBA8
+    }
+}
+
+/// Replace the storage of the texture bound to `target` with `pixels`.
+pub fn upload_texture(gl: &dyn Gl, target: GLenum, pixels: &Pixels) {
+    assert_eq!(pixels.depth, 1);
+    with_default_unpack_state(gl, || {
+        gl.tex_image_2d(
+            target,
+            0,
+            internal_format(pixels) as GLint,
+            pixels.width as GLsizei,
+            pixels.height as GLsizei,
+            0,
+            pixels.format,
+            pixels.pixel_type,
+            Some(&pixels.bytes),
+        );
+    });
+}
+
+/// Replace the storage of the renderbuffer bound to `target` with `pixels`.
+///
+/// There's no way to upload pixels to a renderbuffer directly, so we upload
+/// them to a temporary texture, and blit from that.
+pub fn upload_renderbuffer(gl: &dyn Gl, target: GLenum, pixels: &Pixels) {
+    let width = pixels.width as GLsizei;
+    let height = pixels.height as GLsizei;
+    gl.renderbuffer_storage(target, internal_format(pixels), width, height);
+
+    let renderbuffer = get_integer(gl, gl::RENDERBUFFER_BINDING) as GLuint;
+    let saved_texture = get_integer(gl, gl::TEXTURE_BINDING_2D) as GLuint;
+    let saved_read_framebuffer = get_integer(gl, gl::READ_FRAMEBUFFER_BINDING) as GLuint;
+    let saved_draw_framebuffer = get_integer(gl, gl::DRAW_FRAMEBUFFER_BINDING) as GLuint;
+    let scissor_test = gl.is_enabled(gl::SCISSOR_TEST) != 0;
+
+    let texture = gl.gen_textures(1)[0];
+    gl.bind_texture(gl::TEXTURE_2D, texture);
+    upload_texture(gl, gl::TEXTURE_2D, pixels);
+
+    let framebuffers = gl.gen_framebuffers(2);
+    gl.bind_framebuffer(gl::READ_FRAMEBUFFER, framebuffers[0]);
+    gl.framebuffer_texture_2d(
+        gl::READ_FRAMEBUFFER,
+        gl::COLOR_ATTACHMENT0,
+        gl::TEXTURE_2D,
+        texture,
+        0,
+    );
+    gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, framebuffers[1]);
+    gl.framebuffer_renderbuffer(
+        gl::DRAW_FRAMEBUFFER,
+        gl::COLOR_ATTACHMENT0,
+        gl::RENDERBUFFER,
+        renderbuffer,
+    );
+    if scissor_test {
+        gl.disable(gl::SCISSOR_TEST);
+    }
+    gl.blit_framebuffer(
+        0,
+        0,
+        width,
+        height,
+        0,
+        0,
+        width,
+        height,
+        gl::COLOR_BUFFER_BIT,
+        gl::NEAREST,
+    );
+
+    if scissor_test {
+        gl.enable(gl::SCISSOR_TEST);
+    }
+    gl.bind_framebuffer(gl::READ_FRAMEBUFFER, saved_read_framebuffer);
+    gl.bind_framebuffer(gl::DRAW_FRAMEBUFFER, saved_draw_framebuffer);
+    gl.delete_framebuffers(&framebuffers);
+    gl.bind_texture(gl::TEXTURE_2D, saved_texture);
+    gl.delete_textures(&[texture]);
+}
+
+/// Replace any `TEXTURE_EXTERNAL_OES` texture targets in `call` with
+/// `TEXTURE_2D`.
+pub fn external_targets_as_2d(call: &mut Call) {
+    use Call::*;
+
+    let as_2d = |target: &mut GLenum| {
+        if *target == gl::TEXTURE_EXTERNAL_OES {
+            *target = gl::TEXTURE_2D;
+        }
+    };
+    match call {
+        bind_texture { target, .. }
+        | tex_parameter_f { target, .. }
+        | tex_parameter_i { target, .. }
+        | get_tex_parameter_iv { target, .. }
+        | get_tex_parameter_fv { target, .. }
+        | generate_mipmap { target }
+        | egl_image_target_texture2d_oes { target, .. } => as_2d(target),
+        framebuffer_texture_2d { textarget, .. } => as_2d(textarget),
+        copy_texture_chromium { dest_target, .. }
+        | copy_sub_texture_chromium { dest_target, .. } => as_2d(dest_target),
+        copy_image_sub_data {
+            src_target,
+            dst_target,
+            ..
+        } => {
+            as_2d(src_target);
+            as_2d(dst_target);
+        }
+        _ => {}
+    }
+}
+
+/// Set `shader`'s source to `strings`, with any `samplerExternalOES`
+/// declarations changed to `sampler2D`, to match `external_targets_as_2d`.
+///
+/// The `#extension` directives enabling external samplers are commented out,
+/// since the replay `Gl` may not support them.
+pub fn shader_source(gl: &dyn Gl, shader: GLuint, strings: &[&[u8]]) {
+    let strings: Vec<Cow<[u8]>> = strings
+        .iter()
+        .map(|string| external_samplers_as_2d(string))
+        .collect();
+    let strings: Vec<&[u8]> = strings.iter().map(|string| string.as_ref()).collect();
+    gl.shader_source(shader, &strings);
+}
+
+const EXTERNAL_EXTENSION: &[u8] = b"GL_OES_EGL_image_external";
+
+fn external_samplers_as_2d(source: &[u8]) -> Cow<'_, [u8]> {
+    const EXTERNAL: &[u8] = b"samplerExternalOES";
+    const TWO_D: &[u8] = b"sampler2D";
+
+    if !contains(source, EXTERNAL) && !contains(source, EXTERNAL_EXTENSION) {
+        return Cow::Borrowed(source);
+    }
+    let mut rewritten = Vec::with_capacity(source.len());
+    for line in source.split_inclusive(|&byte| byte == b'\n') {
+        // Comment out the directive rather than deleting it, so that line
+        // numbers in compilation errors still match the recorded source.
+        if is_external_extension_directive(line) {
+            rewritten.extend_from_slice(b"// ");
+            rewritten.extend_from_slice(line);
+            continue;
+        }
+        let mut rest = line;
+        while !rest.is_empty() {
+            if rest.starts_with(EXTERNAL) {
+                rewritten.extend_from_slice(TWO_D);
+                rest = &rest[EXTERNAL.len()..];
+            } else {
+                rewritten.push(rest[0]);
+                rest = &rest[1..];
+            }
+        }
+    }
+    Cow::Owned(rewritten)
+}
+
+/// Return true if `line` is an `#extension` directive for
+/// `GL_OES_EGL_image_external` or `GL_OES_EGL_image_external_essl3`.
+fn is_external_extension_directive(line: &[u8]) -> bool {
+    let line = trim_start(line);
+    if !line.starts_with(b"#") {
+        return false;
+    }
+    trim_start(&line[1..]).starts_with(b"extension") && contains(line, EXTERNAL_EXTENSION)
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+#[test]
+fn test_external_samplers_as_2d() {
+    let source: &[u8] = b"uniform samplerExternalOES sColor;\nuniform sampler2D sMask;\n";
+    assert_eq!(
+        external_samplers_as_2d(source).as_ref(),
+        &b"uniform sampler2D sColor;\nuniform sampler2D sMask;\n"[..]
+    );
+    assert!(matches!(
+        external_samplers_as_2d(b"uniform sampler2D sMask;"),
+        Cow::Borrowed(_)
+    ));
+
+    let source: &[u8] = b"#version 300 es\n\
+                          #extension GL_OES_EGL_image_external : require\n\
+                          # extension GL_OES_EGL_image_external_essl3 : require\n\
+                          #extension GL_EXT_blend_func_extended : enable\n\
+                          uniform samplerExternalOES sColor;";
+    assert_eq!(
+        external_samplers_as_2d(source).as_ref(),
+        &b"#version 300 es\n\
+           // #extension GL_OES_EGL_image_external : require\n\
+           // # extension GL_OES_EGL_image_external_essl3 : require\n\
+           #extension GL_EXT_blend_func_extended : enable\n\
+           uniform sampler2D sColor;"[..]
+    );
+}
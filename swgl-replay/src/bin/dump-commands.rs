@@ -3,11 +3,15 @@ use serde::Deserialize;
 use std::io;
 
 use swgl_replay::{Call, FileRecording};
+use gl_replay::dump::{self, GroupNesting};
 use gl_replay::replay;
 
 const USAGE: &'static str = "
 Dump swgl-replay command log.
 
+Calls between matching debug group or group marker pushes and pops are
+indented by their nesting depth.
+
 Usage:
   dump-commands <dir>...
 ";
@@ -31,14 +35,22 @@ fn main() -> io::Result<()> {
             Ok(recording) => recording,
         };
 
+        let mut nesting = GroupNesting::default();
         for (i, call) in recording.calls.iter().enumerate() {
             match call {
                 Call::note(note) => {
                     let note: &str = replay::get_parameter(*note, &recording.variable);
-                    println!("{:4} note: {:?}", i, note);
+                    println!("{:4} {}note: {:?}", i, nesting.current_indent(), note);
+                }
+                Call::gl(gl_call) => {
+                    let indent = nesting.indent(gl_call);
+                    match dump::describe_marker(gl_call, &recording.variable) {
+                        Some(marker) => println!("{:4} {}{}", i, indent, marker),
+                        None => println!("{:4} {}{:?}", i, indent, call),
+                    }
                 }
                 other => {
-                    println!("{:4} {:?}", i, other);
+                    println!("{:4} {}{:?}", i, nesting.current_indent(), other);
                 }
             }
         }
@@ -1,8 +1,10 @@
 use docopt::Docopt;
+use gl_replay::compressed::CompressedFormat;
 use gl_replay::pixels::Pixels;
 use gl_replay::replay::Parameter;
 use gl_replay::Call as GlCall;
 use gl_replay::TexImageData;
+use gleam::gl::{GLenum, GLsizei};
 use serde::Deserialize;
 use swgl_replay::Call as SwglCall;
 use swgl_replay::FileRecording;
@@ -18,6 +20,10 @@ Scan the log of SWGL and OpenGL calls for uses of the `read_pixels_into_buffer`
 method. For each call, write an image named `read_pixels_into_buffer-N.png`,
 where N is the method call's serial number in the log.
 
+Compressed texture uploads can't be written out as images, so for each
+`compressed_tex_image_2d` or `compressed_tex_sub_image_2d` call, just print
+its serial number, size, format name, and block dimensions.
+
 Usage:
   dump-images <dir>
 ";
@@ -62,6 +68,16 @@ fn main() -> io::Result<()> {
                 pixels.write_image(&filename);
                 *kinds.entry("tex_sub_image_3d_pbo").or_insert(0) += 1;
             }
+            SwglCall::gl(GlCall::compressed_tex_image_2d {
+                width, height, internal_format: format, ..
+            }) => {
+                describe_compressed(i, "compressed_tex_image_2d", width, height, format);
+            }
+            SwglCall::gl(GlCall::compressed_tex_sub_image_2d {
+                width, height, format, ..
+            }) => {
+                describe_compressed(i, "compressed_tex_sub_image_2d", width, height, format);
+            }
             _ => (),
         }
     }
@@ -72,3 +88,16 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+fn describe_compressed(serial: usize, kind: &str, width: GLsizei, height: GLsizei, format: GLenum) {
+    match CompressedFormat::from_gl(format) {
+        Some(format) => println!(
+            "{}-{}: {}x{} {}, {}x{} blocks",
+            kind, serial, width, height, format.name, format.block_width, format.block_height
+        ),
+        None => println!(
+            "{}-{}: {}x{} unknown compressed format 0x{:x}",
+            kind, serial, width, height, format
+        ),
+    }
+}
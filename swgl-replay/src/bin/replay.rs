@@ -9,12 +9,31 @@ static USAGE: &str = "
 Replay swgl-replay command log.
 
 Usage:
-  swgl-replay <dir>
+  swgl-replay [options] <dir>
+
+Options:
+  --relink-program-binaries  When a recorded program binary is rejected,
+                             link the program from its recorded sources.
+  --continue-on-mismatch     Don't stop at the first call whose results
+                             differ from the recording; report them all at
+                             the end.
+  --start=<serial>           Fast-forward through the calls before the one
+                             numbered <serial>, replaying only those that
+                             establish state, not draws or readbacks. Since
+                             skipped draws leave framebuffer contents
+                             unknown, later fingerprints and pixel
+                             readbacks are not compared.
+  --end=<serial>             Stop before the call numbered <serial>. Must
+                             not be less than --start.
 ";
 
 #[derive(Debug, Deserialize)]
 struct Args {
     arg_dir: String,
+    flag_relink_program_binaries: bool,
+    flag_continue_on_mismatch: bool,
+    flag_start: Option<usize>,
+    flag_end: Option<usize>,
 }
 
 fn main() -> io::Result<()> {
@@ -27,6 +46,30 @@ fn main() -> io::Result<()> {
     let swgl = Context::create();
     swgl.make_current();
 
-    ReplayState::from_swgl(swgl).replay(&recording.calls, &recording.variable);
+    let gl_replayer = gl_replay::Replayer::new()
+        .relink_rejected_program_binaries(args.flag_relink_program_binaries)
+        .continue_on_mismatch(args.flag_continue_on_mismatch);
+
+    let start = args.flag_start.unwrap_or(0);
+    let end = args.flag_end.unwrap_or(recording.calls.len());
+    if start > end {
+        eprintln!("swgl-replay: --start={} is after --end={}", start, end);
+        std::process::exit(1);
+    }
+
+    let mut state = ReplayState::from_swgl(swgl).with_gl_replayer(gl_replayer);
+    if let Err(err) = state.replay_range(&recording.calls, &recording.variable, start..end) {
+        eprintln!("swgl-replay: {}", err);
+        std::process::exit(1);
+    }
+
+    let divergences = state.divergences();
+    for divergence in divergences {
+        eprintln!("swgl-replay: {}", divergence);
+    }
+    if !divergences.is_empty() {
+        eprintln!("swgl-replay: {} calls diverged from the recording", divergences.len());
+        std::process::exit(1);
+    }
     Ok(())
 }
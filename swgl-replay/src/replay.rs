@@ -1,48 +1,123 @@
+use gl_replay::{Namespace, ReplayError};
 use gleam::gl::GLuint;
 
 use crate::Call;
 
 use std::collections::HashMap;
 use std::ffi::c_void;
+use std::ops::Range;
 
 pub struct ReplayState {
     swgl: swgl::Context,
+    gl_replayer: gl_replay::Replayer,
     borrowed_buffers: HashMap<GLuint, Vec<u8>>,
     default_frame_buffer: Option<Vec<u8>>,
+
+    /// True if fast-forwarding has skipped a composite.
+    skipped_composite: bool,
 }
 
 impl ReplayState {
     pub fn from_swgl(swgl: swgl::Context) -> ReplayState {
         ReplayState {
             swgl,
+            gl_replayer: gl_replay::Replayer::new(),
             borrowed_buffers: HashMap::new(),
             default_frame_buffer: None,
+            skipped_composite: false,
         }
     }
 
+    /// Use `gl_replayer` to replay the `Gl` calls in the recording.
+    pub fn with_gl_replayer(mut self, gl_replayer: gl_replay::Replayer) -> ReplayState {
+        self.gl_replayer = gl_replayer;
+        self
+    }
+
     pub fn into_swgl(self) -> swgl::Context {
         self.swgl
     }
 
-    pub fn replay(&mut self, calls: &[Call], variable: &[u8]) {
-        for (serial, call) in calls.iter().enumerate() {
-            self.replay_one(call, variable, serial);
+    /// Return the divergences from the recording collected so far.
+    ///
+    /// See `gl_replay::Replayer::continue_on_mismatch`.
+    pub fn divergences(&self) -> &[ReplayError] {
+        self.gl_replayer.divergences()
+    }
+
+    pub fn replay(&mut self, calls: &[Call], variable: &[u8]) -> Result<(), ReplayError> {
+        self.replay_range(calls, variable, 0..calls.len())
+    }
+
+    /// Replay the calls whose serial numbers fall in `range`.
+    ///
+    /// The calls before `range` are fast-forwarded: those that only draw,
+    /// composite, read back results, or check fingerprints are skipped. See
+    /// `gl_replay::Replayer::fast_forward_one`. Once a draw or composite has
+    /// been skipped, the contents of framebuffers may not match the recording,
+    /// so later fingerprints and color buffers aren't compared.
+    ///
+    /// A `range` that starts after it ends is treated as empty: the calls
+    /// before its end are fast-forwarded, and none are replayed in full.
+    pub fn replay_range(
+        &mut self,
+        calls: &[Call],
+        variable: &[u8],
+        range: Range<usize>,
+    ) -> Result<(), ReplayError> {
+        let start = range.start.min(range.end);
+        for (serial, call) in calls.iter().enumerate().take(range.end) {
+            self.replay_one(call, variable, serial, serial < start)?;
         }
+        Ok(())
+    }
+
+    /// Return true if fast-forwarding has skipped anything that draws.
+    fn skipped_rendering(&self) -> bool {
+        self.skipped_composite || self.gl_replayer.skipped_rendering()
     }
 
     #[allow(unused_variables)]
-    fn replay_one(&mut self, call: &Call, variable: &[u8], serial: usize) {
+    fn replay_one(
+        &mut self,
+        call: &Call,
+        variable: &[u8],
+        serial: usize,
+        fast_forward: bool,
+    ) -> Result<(), ReplayError> {
         let call = *call;
         use Call::*;
+        if fast_forward {
+            match call {
+                fingerprint(..) | get_color_buffer { .. } => return Ok(()),
+                composite { .. } => {
+                    self.skipped_composite = true;
+                    return Ok(());
+                }
+                gl(gl_call) => {
+                    return self
+                        .gl_replayer
+                        .fast_forward_one(&self.swgl, &gl_call, variable, serial);
+                }
+                _ => {}
+            }
+        }
         match call {
             note(..) => (),
             fingerprint(expected) => {
                 let actual = crate::fingerprinter::fingerprint(&self.swgl);
-                if expected != actual {
-                    panic!("SWGL fingerprints diverged by serial {}", serial);
+                if expected != actual && !self.skipped_rendering() {
+                    self.gl_replayer.report_divergence(ReplayError {
+                        serial,
+                        method: "fingerprint",
+                        expected: format!("{:#x}", expected),
+                        actual: format!("{:#x}", actual),
+                    })?;
                 }
             }
-            gl(gl_call) => gl_replay::replay_one(&self.swgl, &gl_call, variable, serial),
+            gl(gl_call) => self
+                .gl_replayer
+                .replay_one(&self.swgl, &gl_call, variable, serial)?,
             init_default_framebuffer { width, height, stride, buf } => {
                 let buf: Option<Vec<u8>> = gl_replay::replay::get_parameter(buf, variable);
                 let buf = match buf {
@@ -69,15 +144,22 @@ impl ReplayState {
                     let buf: &[u32] = gl_replay::replay::get_parameter(buf, variable);
                     assert!(buf.len() != stride as usize * height as usize);
                 };
+                let fbo = self.gl_replayer.live_name(Namespace::Framebuffer, fbo);
                 let actual = self.swgl.get_color_buffer(fbo, flush);
                 let actual_buf = {
                     let (buf, _width, height, stride) = actual;
                     unsafe { std::slice::from_raw_parts(buf, stride as usize * height as usize) };
                 };
                 if (expected_buf, expected.1, expected.2, expected.3) !=
-                    (actual_buf, actual.1, actual.2, actual.3)
+                    (actual_buf, actual.1, actual.2, actual.3) &&
+                    !self.skipped_rendering()
                 {
-                    panic!("get_color_buffer return value doesn't match expectations");
+                    self.gl_replayer.report_divergence(ReplayError {
+                        serial,
+                        method: "get_color_buffer",
+                        expected: format!("{:?}", (expected.1, expected.2, expected.3)),
+                        actual: format!("{:?}", (actual.1, actual.2, actual.3)),
+                    })?;
                 }
             }
             set_texture_buffer {
@@ -90,6 +172,7 @@ impl ReplayState {
                 min_width,
                 min_height,
             } => {
+                let tex = self.gl_replayer.live_name(Namespace::Texture, tex);
                 let buf: Option<Vec<u8>> = gl_replay::replay::get_parameter(buf, variable);
                 let buf = match buf {
                     None => {
@@ -125,8 +208,10 @@ impl ReplayState {
                 opaque,     // : bool,
                 flip,       // : bool,
             } => self.swgl.composite(
-                src_id, src_x, src_y, src_width, src_height, dst_x, dst_y, opaque, flip,
+                self.gl_replayer.live_name(Namespace::Texture, src_id),
+                src_x, src_y, src_width, src_height, dst_x, dst_y, opaque, flip,
             ),
         }
+        Ok(())
     }
 }